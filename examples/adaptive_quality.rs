@@ -0,0 +1,100 @@
+/// Shatters a large wave of asteroids at once. The events are spread over several frames to
+/// stay under the fragment budget, and the number of particles drops while the frame time is
+/// over 22ms, which leaves headroom above the refresh interval of a 60Hz display. The quality and
+/// the number of deferred events are logged as diagnostics.
+use bevy::diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin};
+use bevy::prelude::*;
+use bevy_despawn_particles::prelude::*;
+
+const COLUMNS: usize = 16;
+const ROWS: usize = 10;
+
+#[derive(Component, Default)]
+pub struct Marker;
+
+pub struct MyTimer(pub Timer);
+
+impl Default for MyTimer {
+    fn default() -> Self {
+        Self(Timer::from_seconds(0.5, TimerMode::Once))
+    }
+}
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugins((
+            FrameTimeDiagnosticsPlugin,
+            LogDiagnosticsPlugin::filtered(vec![
+                FrameTimeDiagnosticsPlugin::FRAME_TIME,
+                AdaptiveQuality::QUALITY,
+                AdaptiveQuality::DEFERRED_EVENTS,
+            ]),
+        ))
+        .add_plugins(DespawnParticlesPlugin)
+        .insert_resource(DespawnParticlesConfig {
+            max_particles: 8192,
+            adaptive_quality: Some(
+                AdaptiveQuality::new()
+                    .with_max_frame_time(1.0 / 45.0)
+                    .with_max_fragments_per_frame(1024),
+            ),
+            ..default()
+        })
+        .add_systems(Startup, setup)
+        .add_systems(Update, tick)
+        .run();
+}
+
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn(Camera2d::default());
+    spawn_wave(&mut commands, &asset_server);
+}
+
+fn spawn_wave(commands: &mut Commands, asset_server: &AssetServer) {
+    for column in 0..COLUMNS {
+        for row in 0..ROWS {
+            commands.spawn((
+                Sprite::from_image(asset_server.load("asteroid_round.png")),
+                Transform::from_xyz(
+                    (column as f32 - (COLUMNS - 1) as f32 / 2.0) * 70.0,
+                    (row as f32 - (ROWS - 1) as f32 / 2.0) * 70.0,
+                    0.0,
+                )
+                .with_scale(Vec3::splat(0.25)),
+                Marker,
+            ));
+        }
+    }
+}
+
+fn tick(
+    mut timer: Local<MyTimer>,
+    time: Res<Time>,
+    mut despawn_particles_event_writer: EventWriter<DespawnParticlesEvent>,
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    marker: Query<Entity, With<Marker>>,
+) {
+    timer.0.tick(time.delta());
+    if timer.0.just_finished() {
+        if !marker.is_empty() {
+            for entity in marker.iter() {
+                despawn_particles_event_writer.send(
+                    DespawnParticlesEvent::builder()
+                        .with_linvel(50.0..150.0)
+                        .with_angvel(-5.0..5.0)
+                        .with_lifetime(1.5)
+                        .with_fade(true)
+                        .with_target_num_particles(64)
+                        .build(entity),
+                );
+            }
+            timer.0 = Timer::from_seconds(2.0, TimerMode::Once);
+            timer.0.reset();
+        } else {
+            spawn_wave(&mut commands, &asset_server);
+            timer.0 = Timer::from_seconds(0.5, TimerMode::Once);
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use bevy::prelude::*;
+use bevy_despawn_particles::prelude::*;
+
+#[derive(Component, Default)]
+pub struct Marker;
+
+pub struct MyTimer(pub Timer);
+
+impl Default for MyTimer {
+    fn default() -> Self {
+        Self(Timer::from_seconds(0.5, TimerMode::Once))
+    }
+}
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugins(DespawnParticlesPlugin)
+        .add_systems(Startup, setup)
+        .add_systems(Update, tick)
+        .run();
+}
+
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn((
+        Camera2d::default(),
+        Camera {
+            clear_color: ClearColorConfig::Custom(Color::BLACK),
+            ..default()
+        },
+    ));
+    commands.spawn((
+        Sprite::from_image(asset_server.load("asteroid_round.png")),
+        Marker,
+    ));
+}
+
+fn tick(
+    mut timer: Local<MyTimer>,
+    time: Res<Time>,
+    mut despawn_particles_event_writer: EventWriter<DespawnParticlesEvent>,
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    marker: Query<Entity, With<Marker>>,
+) {
+    timer.0.tick(time.delta());
+    if timer.0.just_finished() {
+        if let Ok(entity) = marker.get_single() {
+            despawn_particles_event_writer.send(
+                DespawnParticlesEvent::builder()
+                    .with_fade(true)
+                    .with_linvel(50.0..150.0)
+                    .with_blend_mode(DespawnBlendMode::Additive)
+                    .build(entity),
+            );
+            timer.0 = Timer::from_seconds(1.2, TimerMode::Once);
+            timer.0.reset();
+        } else {
+            commands.spawn((
+                Sprite::from_image(asset_server.load("asteroid_round.png")),
+                Marker,
+            ));
+            timer.0 = Timer::from_seconds(0.5, TimerMode::Once);
+        }
+    }
+}
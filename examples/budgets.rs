@@ -0,0 +1,106 @@
+/// The asteroids on the left count towards a small "environment" budget that fades out its
+/// oldest particles, while the one on the right uses its own "player" budget and is never
+/// evicted by the others.
+use bevy::prelude::*;
+use bevy_despawn_particles::prelude::*;
+
+#[derive(Component, Default)]
+pub struct Environment;
+
+#[derive(Component, Default)]
+pub struct Player;
+
+pub struct MyTimer(pub Timer);
+
+impl Default for MyTimer {
+    fn default() -> Self {
+        Self(Timer::from_seconds(0.5, TimerMode::Once))
+    }
+}
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugins(DespawnParticlesPlugin)
+        .add_systems(Startup, setup)
+        .add_systems(Update, tick)
+        .insert_resource(
+            DespawnParticlesConfig::default()
+                .with_budget(
+                    "environment",
+                    ParticleBudget::new(256).with_eviction(EvictionPolicy::FadeOut),
+                )
+                .with_budget(
+                    "player",
+                    ParticleBudget::new(128)
+                        .with_eviction(EvictionPolicy::Smallest)
+                        .with_eviction_duration(0.3),
+                ),
+        )
+        .run();
+}
+
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn(Camera2d::default());
+    spawn(&mut commands, &asset_server);
+}
+
+fn tick(
+    mut timer: Local<MyTimer>,
+    time: Res<Time>,
+    mut despawn_particles_event_writer: EventWriter<DespawnParticlesEvent>,
+    mut commands: Commands,
+    environment: Query<Entity, With<Environment>>,
+    player: Query<Entity, With<Player>>,
+    asset_server: Res<AssetServer>,
+) {
+    timer.0.tick(time.delta());
+    if timer.0.just_finished() {
+        if !environment.is_empty() || !player.is_empty() {
+            for entity in environment.iter() {
+                despawn_particles_event_writer.send(
+                    DespawnParticlesEvent::builder()
+                        .with_fade(true)
+                        .with_linvel(50.0..65.0)
+                        .with_angvel(-3.0..3.0)
+                        .with_lifetime(15.0)
+                        .with_target_num_particles(128)
+                        .with_budget("environment")
+                        .build(entity),
+                );
+            }
+            for entity in player.iter() {
+                despawn_particles_event_writer.send(
+                    DespawnParticlesEvent::builder()
+                        .with_fade(true)
+                        .with_linvel(50.0..65.0)
+                        .with_angvel(-3.0..3.0)
+                        .with_lifetime(15.0)
+                        .with_target_num_particles(64)
+                        .with_budget("player")
+                        .build(entity),
+                );
+            }
+            timer.0 = Timer::from_seconds(1.5, TimerMode::Once);
+            timer.0.reset();
+        } else {
+            spawn(&mut commands, &asset_server);
+            timer.0 = Timer::from_seconds(0.5, TimerMode::Once);
+        }
+    }
+}
+
+fn spawn(commands: &mut Commands, asset_server: &AssetServer) {
+    for y in [-150.0, 0.0, 150.0] {
+        commands.spawn((
+            Sprite::from_image(asset_server.load("asteroid_round.png")),
+            Transform::from_xyz(-250.0, y, 0.0),
+            Environment,
+        ));
+    }
+    commands.spawn((
+        Sprite::from_image(asset_server.load("asteroid_round.png")),
+        Transform::from_xyz(250.0, 0.0, 0.0),
+        Player,
+    ));
+}
@@ -0,0 +1,62 @@
+use bevy::prelude::*;
+use bevy_despawn_particles::prelude::*;
+
+#[derive(Component, Default)]
+pub struct Marker;
+
+pub struct MyTimer(pub Timer);
+
+impl Default for MyTimer {
+    fn default() -> Self {
+        Self(Timer::from_seconds(0.5, TimerMode::Once))
+    }
+}
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugins(DespawnParticlesPlugin)
+        .add_systems(Startup, setup)
+        .add_systems(Update, tick)
+        .run();
+}
+
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn(Camera2d::default());
+    commands.spawn((
+        Sprite::from_image(asset_server.load("asteroid_round.png")),
+        Marker,
+    ));
+}
+
+fn tick(
+    mut timer: Local<MyTimer>,
+    time: Res<Time>,
+    mut despawn_particles_event_writer: EventWriter<DespawnParticlesEvent>,
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    marker: Query<Entity, With<Marker>>,
+) {
+    timer.0.tick(time.delta());
+    if timer.0.just_finished() {
+        if let Ok(entity) = marker.get_single() {
+            despawn_particles_event_writer.send(
+                DespawnParticlesEvent::builder()
+                    .with_color_over_lifetime([
+                        (0.0, Color::WHITE),
+                        (0.3, Color::srgb(1.0, 0.5, 0.0)),
+                        (1.0, Color::srgba(0.1, 0.1, 0.1, 0.0)),
+                    ])
+                    .build(entity),
+            );
+            timer.0 = Timer::from_seconds(1.5, TimerMode::Once);
+            timer.0.reset();
+        } else {
+            commands.spawn((
+                Sprite::from_image(asset_server.load("asteroid_round.png")),
+                Marker,
+            ));
+            timer.0 = Timer::from_seconds(0.5, TimerMode::Once);
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use bevy::prelude::*;
+use bevy_despawn_particles::prelude::*;
+
+#[derive(Component, Default)]
+pub struct Marker;
+
+pub struct MyTimer(pub Timer);
+
+impl Default for MyTimer {
+    fn default() -> Self {
+        Self(Timer::from_seconds(0.5, TimerMode::Once))
+    }
+}
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugins(DespawnParticlesPlugin)
+        .add_systems(Startup, setup)
+        .add_systems(Update, tick)
+        .run();
+}
+
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn(Camera2d::default());
+    spawn_character(&mut commands, &asset_server);
+}
+
+// A character built from several parts, where the root itself has nothing to draw.
+fn spawn_character(commands: &mut Commands, asset_server: &AssetServer) {
+    commands
+        .spawn((Transform::default(), Visibility::default(), Marker))
+        .with_children(|parent| {
+            parent.spawn((
+                Sprite::from_color(Color::srgb(0.2, 0.4, 0.8), Vec2::new(60.0, 100.0)),
+                Transform::from_xyz(0.0, -30.0, 0.0),
+            ));
+            parent.spawn((
+                Sprite::from_image(asset_server.load("asteroid_round.png")),
+                Transform::from_xyz(0.0, 60.0, 1.0).with_scale(Vec3::splat(0.5)),
+            ));
+            parent.spawn((
+                Sprite::from_color(Color::srgb(0.7, 0.7, 0.7), Vec2::new(10.0, 90.0)),
+                Transform::from_xyz(50.0, -10.0, 2.0).with_rotation(Quat::from_rotation_z(-0.4)),
+            ));
+        });
+}
+
+fn tick(
+    mut timer: Local<MyTimer>,
+    time: Res<Time>,
+    mut despawn_particles_event_writer: EventWriter<DespawnParticlesEvent>,
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    marker: Query<Entity, With<Marker>>,
+) {
+    timer.0.tick(time.delta());
+    if timer.0.just_finished() {
+        if let Ok(entity) = marker.get_single() {
+            despawn_particles_event_writer.send(
+                DespawnParticlesEvent::builder()
+                    .with_linvel(100.0..200.0)
+                    .with_angvel(-5.0..5.0)
+                    .with_fade(true)
+                    .with_recurse(true)
+                    .build(entity),
+            );
+            timer.0 = Timer::from_seconds(1.2, TimerMode::Once);
+            timer.0.reset();
+        } else {
+            spawn_character(&mut commands, &asset_server);
+            timer.0 = Timer::from_seconds(0.5, TimerMode::Once);
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use bevy::prelude::*;
+use bevy_despawn_particles::prelude::*;
+
+#[derive(Component)]
+pub struct Ship;
+
+#[derive(Component, Default)]
+pub struct Marker;
+
+pub struct MyTimer(pub Timer);
+
+impl Default for MyTimer {
+    fn default() -> Self {
+        Self(Timer::from_seconds(0.5, TimerMode::Once))
+    }
+}
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugins(DespawnParticlesPlugin)
+        .add_systems(Startup, setup)
+        .add_systems(Update, (move_ship, tick))
+        .run();
+}
+
+fn setup(mut commands: Commands) {
+    commands.spawn(Camera2d::default());
+    commands.spawn((
+        Sprite::from_color(Color::srgb(0.3, 0.3, 0.4), Vec2::new(300.0, 60.0)),
+        Transform::from_xyz(-300.0, -100.0, 0.0),
+        Ship,
+    ));
+}
+
+fn move_ship(mut ships: Query<&mut Transform, With<Ship>>, time: Res<Time>) {
+    for mut transform in ships.iter_mut() {
+        transform.translation.x = (time.elapsed_secs() * 0.5).sin() * 300.0;
+    }
+}
+
+fn tick(
+    mut timer: Local<MyTimer>,
+    time: Res<Time>,
+    mut despawn_particles_event_writer: EventWriter<DespawnParticlesEvent>,
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    marker: Query<Entity, With<Marker>>,
+    ship: Query<Entity, With<Ship>>,
+) {
+    timer.0.tick(time.delta());
+    if timer.0.just_finished() {
+        if let Ok(entity) = marker.get_single() {
+            // The turret is a child of the ship, so its debris travels along with the ship.
+            despawn_particles_event_writer.send(
+                DespawnParticlesEvent::builder()
+                    .with_linvel(100.0..200.0)
+                    .with_mass(1.0)
+                    .with_fade(true)
+                    .with_simulation_space(SimulationSpace::SourceParent)
+                    .build(entity),
+            );
+            timer.0 = Timer::from_seconds(1.2, TimerMode::Once);
+            timer.0.reset();
+        } else if let Ok(ship) = ship.get_single() {
+            commands.entity(ship).with_child((
+                Sprite::from_image(asset_server.load("asteroid_round.png")),
+                Transform::from_xyz(0.0, 80.0, 1.0).with_scale(Vec3::splat(0.5)),
+                Marker,
+            ));
+            timer.0 = Timer::from_seconds(0.5, TimerMode::Once);
+        }
+    }
+}
@@ -0,0 +1,92 @@
+/// Smaller asteroids break into fewer pieces, and the one off-screen to the right does not
+/// break into any. The large asteroid at the top opts out of the policy.
+use bevy::prelude::*;
+use bevy_despawn_particles::prelude::*;
+
+#[derive(Component)]
+pub struct Marker(pub &'static str);
+
+#[derive(Component)]
+pub struct FullDetail;
+
+pub struct MyTimer(pub Timer);
+
+impl Default for MyTimer {
+    fn default() -> Self {
+        Self(Timer::from_seconds(0.5, TimerMode::Once))
+    }
+}
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugins(DespawnParticlesPlugin)
+        .insert_resource(DespawnParticlesConfig {
+            lod: Some(LodPolicy::new().with_full_detail_size(200.0)),
+            ..default()
+        })
+        .add_systems(Startup, setup)
+        .add_systems(Update, tick)
+        .run();
+}
+
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn(Camera2d::default());
+    spawn(&mut commands, &asset_server);
+}
+
+fn spawn(commands: &mut Commands, asset_server: &AssetServer) {
+    for (name, x, scale) in [
+        ("large", -300.0, 1.0),
+        ("medium", -50.0, 0.5),
+        ("small", 150.0, 0.25),
+        ("tiny", 280.0, 0.1),
+        ("off-screen", 2000.0, 1.0),
+    ] {
+        commands.spawn((
+            Sprite::from_image(asset_server.load("asteroid_round.png")),
+            Transform::from_xyz(x, -50.0, 0.0).with_scale(Vec3::splat(scale)),
+            Marker(name),
+        ));
+    }
+    commands.spawn((
+        Sprite::from_image(asset_server.load("asteroid_round.png")),
+        Transform::from_xyz(0.0, 200.0, 0.0).with_scale(Vec3::splat(0.25)),
+        Marker("full detail"),
+        FullDetail,
+    ));
+}
+
+fn tick(
+    mut timer: Local<MyTimer>,
+    time: Res<Time>,
+    mut despawn_particles_event_writer: EventWriter<DespawnParticlesEvent>,
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    marker: Query<(Entity, &Marker, Has<FullDetail>)>,
+) {
+    timer.0.tick(time.delta());
+    if timer.0.just_finished() {
+        if !marker.is_empty() {
+            for (entity, &Marker(name), full_detail) in marker.iter() {
+                let mut builder = DespawnParticlesEvent::builder()
+                    .with_linvel(50.0..100.0)
+                    .with_angvel(-5.0..5.0)
+                    .with_fade(true)
+                    .with_target_num_particles(128)
+                    .with_on_spawn(move |_, fragments| {
+                        info!("{name}: {} fragments", fragments.len())
+                    });
+                if full_detail {
+                    builder = builder.with_lod(LodPolicy::disabled());
+                }
+                despawn_particles_event_writer.send(builder.build(entity));
+            }
+            timer.0 = Timer::from_seconds(1.2, TimerMode::Once);
+            timer.0.reset();
+        } else {
+            spawn(&mut commands, &asset_server);
+            timer.0 = Timer::from_seconds(0.5, TimerMode::Once);
+        }
+    }
+}
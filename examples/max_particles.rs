@@ -20,7 +20,10 @@ fn main() {
         .add_plugins(DespawnParticlesPlugin)
         .add_systems(Startup, setup)
         .add_systems(Update, tick)
-        .insert_resource(DespawnParticlesConfig { max_particles: 320 })
+        .insert_resource(DespawnParticlesConfig {
+            max_particles: 320,
+            ..default()
+        })
         .run();
 }
 
@@ -0,0 +1,186 @@
+/// A benchmark for particle pooling. Shatters waves of asteroids with and without pooling, each
+/// in its own run, and reports the frame times of both. Run with `--pooled` or `--unpooled` to
+/// only run one of them.
+use std::process::Command;
+use std::time::Duration;
+
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use bevy_despawn_particles::prelude::*;
+
+const COLUMNS: usize = 10;
+const ROWS: usize = 6;
+
+// Frames during the warmup are not measured.
+const WARMUP_SECS: f32 = 2.0;
+const MEASURE_SECS: f32 = 10.0;
+
+#[derive(Component, Default)]
+pub struct Marker;
+
+pub struct MyTimer(pub Timer);
+
+impl Default for MyTimer {
+    fn default() -> Self {
+        Self(Timer::from_seconds(0.5, TimerMode::Once))
+    }
+}
+
+#[derive(Resource, Default)]
+struct FrameTimes(Vec<Duration>);
+
+fn main() {
+    let args = std::env::args().collect::<Vec<_>>();
+    if args.iter().any(|arg| arg == "--pooled") {
+        run(true);
+    } else if args.iter().any(|arg| arg == "--unpooled") {
+        run(false);
+    } else {
+        compare();
+    }
+}
+
+/// Runs both modes as separate processes, since pooling is set up once at startup.
+fn compare() {
+    let exe = std::env::current_exe().expect("Could not find the example executable");
+    let results = ["--unpooled", "--pooled"].map(|mode| {
+        let output = Command::new(&exe)
+            .arg(mode)
+            .output()
+            .expect("Could not run the benchmark");
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let result = stdout
+            .lines()
+            .find_map(|line| line.strip_prefix("RESULT "))
+            .map(|result| {
+                result
+                    .split_whitespace()
+                    .map(|value| value.parse::<f64>().unwrap_or_default())
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        (mode, result)
+    });
+
+    println!(
+        "{:<12}{:>12}{:>12}{:>10}",
+        "mode", "avg (ms)", "worst (ms)", "frames"
+    );
+    for (mode, result) in results.iter() {
+        if let [average, worst, frames] = result[..] {
+            println!("{mode:<12}{average:>12.2}{worst:>12.2}{frames:>10}");
+        } else {
+            println!("{mode:<12} did not report a result");
+        }
+    }
+    if let ([unpooled, ..], [pooled, ..]) = (&results[0].1[..], &results[1].1[..]) {
+        println!(
+            "Pooling changes the average frame time by {:+.1}%",
+            (pooled - unpooled) / unpooled * 100.0
+        );
+    }
+}
+
+fn run(pooling: bool) {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugins(DespawnParticlesPlugin)
+        .insert_resource(DespawnParticlesConfig {
+            max_particles: 4096,
+            pooling,
+            ..default()
+        })
+        .init_resource::<FrameTimes>()
+        .add_systems(Startup, setup)
+        .add_systems(Update, (tick, measure))
+        .run();
+}
+
+fn setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    config: Res<DespawnParticlesConfig>,
+) {
+    info!("Pooling: {}", config.pooling);
+    commands.spawn(Camera2d::default());
+    spawn_wave(&mut commands, &asset_server);
+}
+
+/// Records the frame times after the warmup, then reports them and exits.
+fn measure(
+    time: Res<Time<Real>>,
+    mut frame_times: ResMut<FrameTimes>,
+    mut app_exit_writer: EventWriter<AppExit>,
+) {
+    let elapsed = time.elapsed_secs();
+    if elapsed < WARMUP_SECS {
+        return;
+    }
+    frame_times.0.push(time.delta());
+    if elapsed >= WARMUP_SECS + MEASURE_SECS {
+        let frames = frame_times.0.len();
+        let average = frame_times.0.iter().sum::<Duration>() / frames as u32;
+        let worst = frame_times.0.iter().max().copied().unwrap_or_default();
+        info!(
+            "Average frame time {:.2}ms, worst {:.2}ms over {} frames",
+            average.as_secs_f64() * 1000.0,
+            worst.as_secs_f64() * 1000.0,
+            frames
+        );
+        println!(
+            "RESULT {} {} {}",
+            average.as_secs_f64() * 1000.0,
+            worst.as_secs_f64() * 1000.0,
+            frames
+        );
+        app_exit_writer.send(AppExit::Success);
+    }
+}
+
+fn spawn_wave(commands: &mut Commands, asset_server: &AssetServer) {
+    for column in 0..COLUMNS {
+        for row in 0..ROWS {
+            commands.spawn((
+                Sprite::from_image(asset_server.load("asteroid_round.png")),
+                Transform::from_xyz(
+                    (column as f32 - (COLUMNS - 1) as f32 / 2.0) * 110.0,
+                    (row as f32 - (ROWS - 1) as f32 / 2.0) * 110.0,
+                    0.0,
+                )
+                .with_scale(Vec3::splat(0.4)),
+                Marker,
+            ));
+        }
+    }
+}
+
+fn tick(
+    mut timer: Local<MyTimer>,
+    time: Res<Time>,
+    mut despawn_particles_event_writer: EventWriter<DespawnParticlesEvent>,
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    marker: Query<Entity, With<Marker>>,
+) {
+    timer.0.tick(time.delta());
+    if timer.0.just_finished() {
+        if !marker.is_empty() {
+            for entity in marker.iter() {
+                despawn_particles_event_writer.send(
+                    DespawnParticlesEvent::builder()
+                        .with_linvel(50.0..150.0)
+                        .with_angvel(-5.0..5.0)
+                        .with_lifetime(1.0)
+                        .with_fade(true)
+                        .with_target_num_particles(64)
+                        .build(entity),
+                );
+            }
+            timer.0 = Timer::from_seconds(0.5, TimerMode::Once);
+            timer.0.reset();
+        } else {
+            spawn_wave(&mut commands, &asset_server);
+            timer.0 = Timer::from_seconds(0.1, TimerMode::Once);
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use bevy::prelude::*;
+use bevy::render::camera::Viewport;
+use bevy::render::view::RenderLayers;
+use bevy_despawn_particles::prelude::*;
+
+// The asteroid is only drawn by the minimap camera in the corner, and so are its particles.
+const MINIMAP_LAYER: usize = 1;
+
+#[derive(Component, Default)]
+pub struct Marker;
+
+pub struct MyTimer(pub Timer);
+
+impl Default for MyTimer {
+    fn default() -> Self {
+        Self(Timer::from_seconds(0.5, TimerMode::Once))
+    }
+}
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugins(DespawnParticlesPlugin)
+        .add_systems(Startup, setup)
+        .add_systems(Update, tick)
+        .run();
+}
+
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn(Camera2d::default());
+    commands.spawn((
+        Camera2d::default(),
+        Camera {
+            order: 1,
+            viewport: Some(Viewport {
+                physical_position: UVec2::ZERO,
+                physical_size: UVec2::new(320, 240),
+                ..default()
+            }),
+            clear_color: ClearColorConfig::Custom(Color::srgb(0.1, 0.1, 0.2)),
+            ..default()
+        },
+        RenderLayers::layer(MINIMAP_LAYER),
+    ));
+    commands.spawn(Sprite::from_color(
+        Color::srgb(0.3, 0.3, 0.3),
+        Vec2::new(400.0, 40.0),
+    ));
+    spawn_asteroid(&mut commands, &asset_server);
+}
+
+fn spawn_asteroid(commands: &mut Commands, asset_server: &AssetServer) {
+    commands.spawn((
+        Sprite::from_image(asset_server.load("asteroid_round.png")),
+        RenderLayers::layer(MINIMAP_LAYER),
+        Marker,
+    ));
+}
+
+fn tick(
+    mut timer: Local<MyTimer>,
+    time: Res<Time>,
+    mut despawn_particles_event_writer: EventWriter<DespawnParticlesEvent>,
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    marker: Query<Entity, With<Marker>>,
+) {
+    timer.0.tick(time.delta());
+    if timer.0.just_finished() {
+        if let Ok(entity) = marker.get_single() {
+            despawn_particles_event_writer.send(
+                DespawnParticlesEvent::builder()
+                    .with_linvel(50.0..150.0)
+                    .with_z_offset(-1.0..1.0)
+                    .with_fade(true)
+                    .build(entity),
+            );
+            timer.0 = Timer::from_seconds(1.2, TimerMode::Once);
+            timer.0.reset();
+        } else {
+            spawn_asteroid(&mut commands, &asset_server);
+            timer.0 = Timer::from_seconds(0.5, TimerMode::Once);
+        }
+    }
+}
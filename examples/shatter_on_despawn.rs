@@ -0,0 +1,62 @@
+/// The sprite carries its own preset and shatters however it ends up being despawned.
+use bevy::prelude::*;
+use bevy_despawn_particles::prelude::*;
+
+#[derive(Component, Default)]
+pub struct Marker;
+
+pub struct MyTimer(pub Timer);
+
+impl Default for MyTimer {
+    fn default() -> Self {
+        Self(Timer::from_seconds(0.5, TimerMode::Once))
+    }
+}
+
+fn main() {
+    App::new()
+        .add_plugins(DefaultPlugins)
+        .add_plugins(DespawnParticlesPlugin)
+        .add_systems(Startup, setup)
+        .add_systems(Update, tick)
+        .run();
+}
+
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn(Camera2d::default());
+    spawn(&mut commands, &asset_server);
+}
+
+fn tick(
+    mut timer: Local<MyTimer>,
+    time: Res<Time>,
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    marker: Query<Entity, With<Marker>>,
+) {
+    timer.0.tick(time.delta());
+    if timer.0.just_finished() {
+        if let Ok(entity) = marker.get_single() {
+            // A plain despawn, no event needed.
+            commands.entity(entity).despawn();
+            timer.0 = Timer::from_seconds(1.2, TimerMode::Once);
+            timer.0.reset();
+        } else {
+            spawn(&mut commands, &asset_server);
+            timer.0 = Timer::from_seconds(0.5, TimerMode::Once);
+        }
+    }
+}
+
+fn spawn(commands: &mut Commands, asset_server: &AssetServer) {
+    commands.spawn((
+        Sprite::from_image(asset_server.load("asteroid_round.png")),
+        ShatterOnDespawn(
+            DespawnParticlesPreset::new()
+                .with_linvel(150.0..250.0)
+                .with_angvel(-5.0..5.0)
+                .with_fade(true),
+        ),
+        Marker,
+    ));
+}
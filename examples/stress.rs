@@ -0,0 +1,102 @@
+/// Shatters 200 asteroids at once and logs the frame time. The fragments are generated in
+/// parallel, run with `--threads <n>` to limit the number of compute threads and compare.
+use bevy::core::TaskPoolThreadAssignmentPolicy;
+use bevy::diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin};
+use bevy::prelude::*;
+use bevy_despawn_particles::prelude::*;
+
+const COLUMNS: usize = 20;
+const ROWS: usize = 10;
+
+#[derive(Component, Default)]
+pub struct Marker;
+
+pub struct MyTimer(pub Timer);
+
+impl Default for MyTimer {
+    fn default() -> Self {
+        Self(Timer::from_seconds(0.5, TimerMode::Once))
+    }
+}
+
+fn main() {
+    let threads = std::env::args()
+        .skip_while(|arg| arg != "--threads")
+        .nth(1)
+        .and_then(|threads| threads.parse::<usize>().ok());
+
+    let mut task_pool_options = TaskPoolOptions::default();
+    if let Some(threads) = threads {
+        info!("Compute threads: {threads}");
+        task_pool_options.compute = TaskPoolThreadAssignmentPolicy {
+            min_threads: threads,
+            max_threads: threads,
+            percent: 1.0,
+        };
+    }
+
+    App::new()
+        .add_plugins(DefaultPlugins.set(TaskPoolPlugin { task_pool_options }))
+        .add_plugins((FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin::default()))
+        .add_plugins(DespawnParticlesPlugin)
+        .insert_resource(DespawnParticlesConfig {
+            max_particles: 16384,
+            ..default()
+        })
+        .add_systems(Startup, setup)
+        .add_systems(Update, tick)
+        .run();
+}
+
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn(Camera2d::default());
+    spawn_wave(&mut commands, &asset_server);
+}
+
+fn spawn_wave(commands: &mut Commands, asset_server: &AssetServer) {
+    for column in 0..COLUMNS {
+        for row in 0..ROWS {
+            commands.spawn((
+                Sprite::from_image(asset_server.load("asteroid_round.png")),
+                Transform::from_xyz(
+                    (column as f32 - (COLUMNS - 1) as f32 / 2.0) * 60.0,
+                    (row as f32 - (ROWS - 1) as f32 / 2.0) * 60.0,
+                    0.0,
+                )
+                .with_scale(Vec3::splat(0.2)),
+                Marker,
+            ));
+        }
+    }
+}
+
+fn tick(
+    mut timer: Local<MyTimer>,
+    time: Res<Time>,
+    mut despawn_particles_event_writer: EventWriter<DespawnParticlesEvent>,
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    marker: Query<Entity, With<Marker>>,
+) {
+    timer.0.tick(time.delta());
+    if timer.0.just_finished() {
+        if !marker.is_empty() {
+            for entity in marker.iter() {
+                despawn_particles_event_writer.send(
+                    DespawnParticlesEvent::builder()
+                        .with_linvel(50.0..150.0)
+                        .with_angvel(-5.0..5.0)
+                        .with_lifetime(1.0)
+                        .with_fade(true)
+                        .with_target_num_particles(64)
+                        .build(entity),
+                );
+            }
+            timer.0 = Timer::from_seconds(1.5, TimerMode::Once);
+            timer.0.reset();
+        } else {
+            spawn_wave(&mut commands, &asset_server);
+            timer.0 = Timer::from_seconds(0.5, TimerMode::Once);
+        }
+    }
+}
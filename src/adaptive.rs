@@ -0,0 +1,207 @@
+//! Lowers the quality of effects under load to keep the frame time stable.
+use std::collections::VecDeque;
+
+use bevy_diagnostic::{DiagnosticPath, Diagnostics};
+use bevy_ecs::system::{Res, ResMut, Resource};
+use bevy_log::{info, warn};
+use bevy_time::{Real, Time};
+
+use crate::{events::DespawnParticlesEvent, resources::DespawnParticlesConfig};
+
+/// The quality is only raised again once the average frame time is below this fraction of
+/// [AdaptiveQuality::max_frame_time], so that it does not keep flipping around the threshold.
+pub const RECOVERY_FRAME_TIME_FACTOR: f32 = 0.9;
+
+/// The least time between two reports of the quality dropping or being restored, in seconds.
+/// The [AdaptiveQuality::QUALITY] diagnostic is updated every frame regardless.
+pub const REPORT_INTERVAL: f32 = 5.0;
+
+/// Lowers the quality of effects while the game is struggling, see
+/// [DespawnParticlesConfig::adaptive_quality].
+#[derive(Clone, Debug)]
+pub struct AdaptiveQuality {
+    /// The average frame time to stay under, in seconds. While the frame time is above this,
+    /// [target_num_particles][crate::events::DespawnParticlesEvent::target_num_particles] of new
+    /// effects is lowered, and it is raised again once the frame time is comfortably below it,
+    /// see [RECOVERY_FRAME_TIME_FACTOR]. Leave some headroom above the refresh interval of the
+    /// display, otherwise vsync alone keeps the quality down. When not set, the frame time is
+    /// ignored, which is the default.
+    pub max_frame_time: Option<f32>,
+
+    /// The most fragments to generate in a single frame. Once it is reached, the remaining
+    /// [DespawnParticlesEvents][crate::events::DespawnParticlesEvent] are deferred to the next
+    /// frames. The budget is checked in between batches of events, so it can be exceeded by up
+    /// to one batch. When not set, events are never deferred.
+    ///
+    /// Only sent events are deferred, the [Commands][bevy_ecs::system::Commands] extensions and
+    /// [ShatterOnDespawn][crate::components::ShatterOnDespawn] are handled right away but still
+    /// count towards this.
+    pub max_fragments_per_frame: Option<usize>,
+
+    /// The lowest quality, as a fraction of the number of particles.
+    pub min_quality: f32,
+
+    /// How much the quality changes per second while adapting.
+    pub adjust_rate: f32,
+
+    /// Events deferred for this many frames are handled regardless of
+    /// [max_fragments_per_frame][AdaptiveQuality::max_fragments_per_frame], so that the
+    /// entities do not linger.
+    pub max_deferred_frames: u32,
+}
+
+impl Default for AdaptiveQuality {
+    fn default() -> Self {
+        Self {
+            max_frame_time: None,
+            max_fragments_per_frame: None,
+            min_quality: 0.25,
+            adjust_rate: 2.0,
+            max_deferred_frames: 10,
+        }
+    }
+}
+
+impl AdaptiveQuality {
+    /// The current quality, from [min_quality][AdaptiveQuality::min_quality] to 1.
+    pub const QUALITY: DiagnosticPath = DiagnosticPath::const_new("despawn_particles/quality");
+
+    /// The number of events currently deferred.
+    pub const DEFERRED_EVENTS: DiagnosticPath =
+        DiagnosticPath::const_new("despawn_particles/deferred_events");
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// See [AdaptiveQuality::max_frame_time]
+    pub fn with_max_frame_time(mut self, max_frame_time: f32) -> Self {
+        self.max_frame_time = Some(max_frame_time);
+        self
+    }
+
+    /// See [AdaptiveQuality::max_fragments_per_frame]
+    pub fn with_max_fragments_per_frame(mut self, max_fragments_per_frame: usize) -> Self {
+        self.max_fragments_per_frame = Some(max_fragments_per_frame);
+        self
+    }
+
+    /// See [AdaptiveQuality::min_quality]
+    pub fn with_min_quality(mut self, min_quality: f32) -> Self {
+        self.min_quality = min_quality;
+        self
+    }
+
+    /// See [AdaptiveQuality::adjust_rate]
+    pub fn with_adjust_rate(mut self, adjust_rate: f32) -> Self {
+        self.adjust_rate = adjust_rate;
+        self
+    }
+
+    /// See [AdaptiveQuality::max_deferred_frames]
+    pub fn with_max_deferred_frames(mut self, max_deferred_frames: u32) -> Self {
+        self.max_deferred_frames = max_deferred_frames;
+        self
+    }
+}
+
+/// The state of [AdaptiveQuality], reset every frame by [update_adaptive_quality].
+#[derive(Resource)]
+pub(crate) struct AdaptiveQualityState {
+    /// Multiplies the number of particles of new effects.
+    pub quality: f32,
+
+    /// The frame time, smoothed over the last few frames.
+    pub average_frame_time: f32,
+
+    /// The fragments generated so far this frame.
+    pub fragments_this_frame: usize,
+
+    /// Events waiting for a later frame, along with the number of frames they have waited.
+    pub deferred: VecDeque<(DespawnParticlesEvent, u32)>,
+
+    /// Whether the quality was last reported as lowered.
+    pub reported_degraded: bool,
+
+    /// When the quality was last reported, in seconds since startup.
+    pub last_report: Option<f32>,
+}
+
+impl Default for AdaptiveQualityState {
+    fn default() -> Self {
+        Self {
+            quality: 1.0,
+            average_frame_time: 0.0,
+            fragments_this_frame: 0,
+            deferred: VecDeque::new(),
+            reported_degraded: false,
+            last_report: None,
+        }
+    }
+}
+
+impl AdaptiveQualityState {
+    /// Whether there is room for more fragments this frame.
+    pub fn has_budget(&self, adaptive_quality: &AdaptiveQuality) -> bool {
+        adaptive_quality
+            .max_fragments_per_frame
+            .is_none_or(|max| self.fragments_this_frame < max)
+    }
+}
+
+/// Adjusts the quality towards the frame time target and reports it.
+pub(crate) fn update_adaptive_quality(
+    config: Res<DespawnParticlesConfig>,
+    mut state: ResMut<AdaptiveQualityState>,
+    time: Res<Time<Real>>,
+    mut diagnostics: Diagnostics,
+) {
+    state.fragments_this_frame = 0;
+    let Some(adaptive_quality) = config.adaptive_quality.as_ref() else {
+        state.quality = 1.0;
+        return;
+    };
+
+    let frame_time = time.delta_secs();
+    state.average_frame_time = if state.average_frame_time > 0.0 {
+        state.average_frame_time * 0.9 + frame_time * 0.1
+    } else {
+        frame_time
+    };
+
+    let change = adaptive_quality.adjust_rate * frame_time;
+    state.quality = match adaptive_quality.max_frame_time {
+        Some(max_frame_time) if state.average_frame_time > max_frame_time => {
+            (state.quality - change).max(adaptive_quality.min_quality)
+        }
+        Some(max_frame_time)
+            if state.average_frame_time > max_frame_time * RECOVERY_FRAME_TIME_FACTOR =>
+        {
+            state.quality
+        }
+        _ => (state.quality + change).min(1.0),
+    };
+
+    let now = time.elapsed_secs();
+    let can_report = state
+        .last_report
+        .is_none_or(|last_report| now - last_report >= REPORT_INTERVAL);
+    let degraded = state.quality < 1.0;
+    if can_report && degraded != state.reported_degraded {
+        if degraded {
+            warn!(
+                "Average frame time of {:.1}ms is over budget, lowering despawn particle quality",
+                state.average_frame_time * 1000.0
+            );
+        } else {
+            info!("Despawn particle quality restored");
+        }
+        state.reported_degraded = degraded;
+        state.last_report = Some(now);
+    }
+
+    diagnostics.add_measurement(&AdaptiveQuality::QUALITY, || state.quality as f64);
+    diagnostics.add_measurement(&AdaptiveQuality::DEFERRED_EVENTS, || {
+        state.deferred.len() as f64
+    });
+}
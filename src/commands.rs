@@ -0,0 +1,54 @@
+//! [Commands] extensions for generating despawn particles without an
+//! [EventWriter][bevy_ecs::event::EventWriter].
+use bevy_ecs::{
+    entity::Entity,
+    system::{Commands, EntityCommands},
+    world::World,
+};
+use bevy_log::error;
+
+use crate::{
+    events::{DespawnParticlesEvent, DespawnParticlesPreset},
+    systems::handle_despawn_particles_command,
+};
+
+/// Extends [Commands] with a way to handle a [DespawnParticlesEvent] as a command.
+pub trait DespawnParticlesCommandsExt {
+    /// Handles the given event when this command is applied, rather than on the next run of the
+    /// [DespawnParticlesSet][crate::DespawnParticlesSet].
+    ///
+    /// The transform, sprite or mesh, and velocity of the target entity are read when the
+    /// command is applied, so this can be used right before another command despawns it.
+    fn despawn_particles(&mut self, event: DespawnParticlesEvent) -> &mut Self;
+}
+
+impl DespawnParticlesCommandsExt for Commands<'_, '_> {
+    fn despawn_particles(&mut self, event: DespawnParticlesEvent) -> &mut Self {
+        self.queue(move |world: &mut World| {
+            handle_event_now(world, event);
+        });
+        self
+    }
+}
+
+/// Extends [EntityCommands] with a way to despawn the entity with particles.
+pub trait DespawnParticlesEntityCommandsExt {
+    /// Creates an event from the given preset for this entity, and handles it when this command
+    /// is applied. See [DespawnParticlesCommandsExt::despawn_particles].
+    fn despawn_with_particles(&mut self, preset: &DespawnParticlesPreset) -> &mut Self;
+}
+
+impl DespawnParticlesEntityCommandsExt for EntityCommands<'_> {
+    fn despawn_with_particles(&mut self, preset: &DespawnParticlesPreset) -> &mut Self {
+        let preset = preset.clone();
+        self.queue(move |entity: Entity, world: &mut World| {
+            handle_event_now(world, preset.build(entity));
+        })
+    }
+}
+
+fn handle_event_now(world: &mut World, event: DespawnParticlesEvent) {
+    if let Err(e) = world.run_system_cached_with(handle_despawn_particles_command, event) {
+        error!("Could not run the despawn particles command: {}", e);
+    }
+}
@@ -1,17 +1,27 @@
 use bevy_render::{mesh::Indices, render_resource::PrimitiveTopology};
 
 use bevy_asset::{Assets, Handle};
-use bevy_ecs::{bundle::Bundle, component::Component, reflect::ReflectComponent};
+use bevy_color::LinearRgba;
+use bevy_ecs::{bundle::Bundle, component::Component, entity::Entity, reflect::ReflectComponent};
+use bevy_math::{Vec2, Vec3};
 use bevy_reflect::Reflect;
-use bevy_render::{mesh::Mesh, render_asset::RenderAssetUsages};
+use bevy_render::{mesh::Mesh, render_asset::RenderAssetUsages, view::Visibility};
 use bevy_time::{Timer, TimerMode};
 
+use std::borrow::Cow;
+
 #[cfg(feature = "bevy_rapier2d")]
 use bevy_rapier2d::prelude::*;
 
 #[cfg(not(feature = "bevy_rapier2d"))]
 use crate::phys::*;
 
+use crate::{
+    curve::LifetimeCurve,
+    events::{DespawnEffectId, DespawnParticlesPreset},
+    gradient::ColorGradient,
+};
+
 /// A particle with an expiration
 #[derive(Component)]
 pub struct DespawnParticle {
@@ -36,11 +46,82 @@ impl Default for DespawnParticle {
 
 /// A despawn particle that will fade as it approaches its expiration
 #[derive(Component, Default)]
-pub(crate) struct FadingDespawnParticle;
+pub(crate) struct FadingDespawnParticle(pub LifetimeCurve);
 
 /// A despawn particle that will shrink as it approaches its expiration
-#[derive(Component, Default)]
-pub(crate) struct ShrinkingDespawnParticle;
+#[derive(Component)]
+pub(crate) struct ShrinkingDespawnParticle {
+    pub curve: LifetimeCurve,
+
+    /// The scale the particle was spawned with, which shrinking is relative to.
+    pub initial_scale: Vec3,
+
+    /// How much shrinking applies to each axis.
+    pub axes: Vec2,
+
+    /// The point to shrink towards, relative to the centroid in the local space of the mesh.
+    pub pivot: Vec2,
+}
+
+/// A despawn particle whose velocity is multiplied by a curve over its lifetime
+#[derive(Component)]
+pub(crate) struct VelocityOverLifetime {
+    pub curve: LifetimeCurve,
+
+    /// The multiplier that was last applied, so only the change is applied to the velocity and
+    /// damping or gravity are preserved.
+    pub previous: f32,
+}
+
+/// The effect a [DespawnParticle] was generated by.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct DespawnParticleEffect {
+    /// The id of the [DespawnParticlesEvent][crate::events::DespawnParticlesEvent] that
+    /// generated this particle.
+    pub effect_id: DespawnEffectId,
+
+    /// The entity this particle was generated from. This entity may no longer exist.
+    pub source: Entity,
+
+    /// The entity holding the [DespawnParticlesEffectRoot] of the effect.
+    pub root: Entity,
+}
+
+/// Groups together all the particles generated by a
+/// [DespawnParticlesEvent][crate::events::DespawnParticlesEvent]. The entity holding this is
+/// despawned once the effect finishes.
+#[derive(Component, Clone, Debug)]
+pub struct DespawnParticlesEffectRoot {
+    /// The id of the event that generated the particles.
+    pub effect_id: DespawnEffectId,
+
+    /// The entity the particles were generated from. This entity may no longer exist.
+    pub source: Entity,
+
+    /// The tag given to the event, see
+    /// [DespawnParticlesEvent::tag][crate::events::DespawnParticlesEvent::tag].
+    pub tag: Option<Cow<'static, str>>,
+
+    /// The particles of this effect that still exist.
+    pub fragments: Vec<Entity>,
+}
+
+/// Tracks an entity that was hidden by a [DespawnParticlesEvent][crate::events::DespawnParticlesEvent]
+/// using one of the hiding [SourceMode][crate::events::SourceMode]s.
+#[derive(Component)]
+pub(crate) struct HiddenDespawnSource {
+    /// The entity is restored or despawned once this effect finishes.
+    pub effect_id: DespawnEffectId,
+
+    /// The visibility to restore once the effect finishes.
+    pub previous_visibility: Visibility,
+
+    /// When true, the entity is despawned instead of being restored once the effect finishes.
+    pub despawn: bool,
+
+    /// When true, and despawn is true, despawns the entity's children as well.
+    pub recurse: bool,
+}
 
 #[derive(Bundle)]
 pub(crate) struct DespawnParticleBundle {
@@ -68,18 +149,46 @@ impl Default for DespawnParticleBundle {
     }
 }
 
-/// Used for ColorMaterial meshes to track what the original alpha value
+/// Used for ColorMaterial meshes to track what the original color
 /// was so it can be properly mixed during fading.
 #[derive(Component, Reflect)]
 #[reflect(Component)]
-pub(crate) struct OriginalAlpha(pub f32);
+pub(crate) struct OriginalColor(pub LinearRgba);
 
-impl Default for OriginalAlpha {
+impl Default for OriginalColor {
     fn default() -> Self {
-        Self(1.0)
+        Self(LinearRgba::WHITE)
+    }
+}
+
+/// A despawn particle that dissolves over its lifetime
+#[derive(Component)]
+pub(crate) struct DissolvingDespawnParticle(pub LifetimeCurve);
+
+/// A despawn particle that starts with a flash of color that decays
+#[derive(Component)]
+pub(crate) struct FlashingDespawnParticle {
+    pub color: LinearRgba,
+
+    /// In seconds
+    pub duration: f32,
+}
+
+impl FlashingDespawnParticle {
+    /// How much of the flash color to mix in, given how long the particle has been alive.
+    pub fn amount(&self, elapsed: f32) -> f32 {
+        if self.duration > 0.0 {
+            (1.0 - elapsed / self.duration).clamp(0.0, 1.0)
+        } else {
+            0.0
+        }
     }
 }
 
+/// A despawn particle whose color is multiplied by a gradient over its lifetime
+#[derive(Component)]
+pub(crate) struct ColorOverLifetime(pub ColorGradient);
+
 /// When present on an Entity, will override the underlying Mesh when creating the
 /// despawn particles. Targetted mostly towards circles since the way they are built do
 /// not break down in a way similar to other shapes.
@@ -134,3 +243,38 @@ impl DespawnMeshOverride {
 #[derive(Component, Default, Reflect)]
 #[reflect(Component)]
 pub struct NoDespawnAnimation;
+
+/// When present on an Entity, particles are generated using the given preset whenever it is
+/// despawned, by any means, without having to send a
+/// [DespawnParticlesEvent][crate::events::DespawnParticlesEvent].
+///
+/// Particles are also generated if this component is removed from the Entity. The
+/// [source_mode][crate::events::DespawnParticlesEvent::source_mode] of the preset is ignored,
+/// the entity is always left to whatever removed this component.
+#[derive(Component, Clone)]
+pub struct ShatterOnDespawn(pub DespawnParticlesPreset);
+
+/// The area of the mesh of a particle, used by
+/// [EvictionPolicy::Smallest][crate::resources::EvictionPolicy::Smallest].
+#[derive(Component)]
+pub(crate) struct FragmentArea(pub f32);
+
+/// A particle evicted from its budget, which fades and shrinks away rather than disappearing,
+/// see [DespawnParticlesConfig::eviction_duration][crate::resources::DespawnParticlesConfig::eviction_duration].
+#[derive(Component)]
+pub(crate) struct EvictedDespawnParticle {
+    /// Runs for the eviction duration.
+    pub timer: Timer,
+
+    /// How much faster than normal the lifetime runs, so it ends along with the timer.
+    pub speed: f32,
+
+    /// The scale of the particle when it was evicted.
+    pub scale: Vec3,
+}
+
+/// Marks an Entity with [ShatterOnDespawn] that is being despawned by a
+/// [DespawnParticlesEvent][crate::events::DespawnParticlesEvent], so particles are not generated
+/// twice.
+#[derive(Component)]
+pub(crate) struct ShatterOnDespawnHandled;
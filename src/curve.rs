@@ -0,0 +1,147 @@
+//! Curves that control how particles change over their lifetime.
+use bevy_math::curve::{Curve, EaseFunction, EasingCurve};
+use serde::{Deserialize, Serialize};
+
+/// Maps the normalized age of a particle, where 0.0 is when the particle is spawned and 1.0 is
+/// when it expires, to a multiplier such as its alpha or scale.
+///
+/// ```
+/// # use bevy_math::curve::EaseFunction;
+/// # use bevy_despawn_particles::curve::LifetimeCurve;
+/// // Hold full opacity, then pop out at the end.
+/// let fade_curve = LifetimeCurve::Ease(EaseFunction::ExponentialIn);
+///
+/// // Grow a bit before shrinking away.
+/// let shrink_curve = LifetimeCurve::keyframes([(0.0, 1.0), (0.2, 1.3), (1.0, 0.0)]);
+/// ```
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub enum LifetimeCurve {
+    /// Goes linearly from 1.0 when spawned to 0.0 when expired.
+    #[default]
+    Linear,
+
+    /// Goes from 1.0 when spawned to 0.0 when expired following the easing function.
+    Ease(EaseFunction),
+
+    /// Blends linearly between the keyframes. Ages outside of the keyframes take the value of
+    /// the closest keyframe.
+    Keyframes(Keyframes),
+}
+
+impl LifetimeCurve {
+    /// Creates a [LifetimeCurve::Keyframes] from pairs of (age, value).
+    pub fn keyframes<I: IntoIterator<Item = (f32, f32)>>(keyframes: I) -> Self {
+        Self::Keyframes(keyframes.into_iter().collect::<Vec<_>>().into())
+    }
+
+    /// Samples the curve at the given normalized age.
+    pub fn sample(&self, age: f32) -> f32 {
+        let age = age.clamp(0.0, 1.0);
+        match self {
+            Self::Linear => 1.0 - age,
+            Self::Ease(ease_function) => {
+                1.0 - EasingCurve::new(0.0, 1.0, *ease_function).sample_clamped(age)
+            }
+            Self::Keyframes(keyframes) => keyframes.sample(age),
+        }
+    }
+}
+
+impl From<EaseFunction> for LifetimeCurve {
+    fn from(ease_function: EaseFunction) -> Self {
+        Self::Ease(ease_function)
+    }
+}
+
+/// Pairs of (age, value), sorted by age. See [LifetimeCurve::Keyframes].
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(from = "Vec<(f32, f32)>", into = "Vec<(f32, f32)>")]
+pub struct Keyframes(Vec<(f32, f32)>);
+
+impl Keyframes {
+    /// The keyframes, sorted by age.
+    pub fn keyframes(&self) -> &[(f32, f32)] {
+        &self.0
+    }
+
+    fn sample(&self, age: f32) -> f32 {
+        let idx = self
+            .0
+            .partition_point(|(keyframe_age, _)| *keyframe_age <= age);
+        match (
+            idx.checked_sub(1).and_then(|idx| self.0.get(idx)),
+            self.0.get(idx),
+        ) {
+            (Some((start_age, start)), Some((end_age, end))) => {
+                let t = (age - start_age) / (end_age - start_age);
+                start + (end - start) * t
+            }
+            (Some((_, value)), None) | (None, Some((_, value))) => *value,
+            (None, None) => 1.0,
+        }
+    }
+}
+
+impl From<Vec<(f32, f32)>> for Keyframes {
+    fn from(mut keyframes: Vec<(f32, f32)>) -> Self {
+        keyframes.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+        Self(keyframes)
+    }
+}
+
+impl From<Keyframes> for Vec<(f32, f32)> {
+    fn from(keyframes: Keyframes) -> Self {
+        keyframes.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_keyframes_sample_one() {
+        assert_eq!(Keyframes::default().sample(0.5), 1.0);
+        assert_eq!(LifetimeCurve::keyframes([]).sample(0.5), 1.0);
+    }
+
+    #[test]
+    fn blends_between_keyframes() {
+        let keyframes = Keyframes::from(vec![(0.0, 1.0), (0.5, 2.0), (1.0, 0.0)]);
+        assert_eq!(keyframes.sample(0.25), 1.5);
+        assert_eq!(keyframes.sample(0.5), 2.0);
+        assert_eq!(keyframes.sample(0.75), 1.0);
+    }
+
+    #[test]
+    fn out_of_range_takes_the_closest_keyframe() {
+        let keyframes = Keyframes::from(vec![(0.25, 2.0), (0.75, 4.0)]);
+        assert_eq!(keyframes.sample(0.0), 2.0);
+        assert_eq!(keyframes.sample(-1.0), 2.0);
+        assert_eq!(keyframes.sample(1.0), 4.0);
+        assert_eq!(keyframes.sample(2.0), 4.0);
+    }
+
+    #[test]
+    fn duplicate_keyframes_make_a_step() {
+        let keyframes = Keyframes::from(vec![(0.0, 1.0), (0.5, 1.0), (0.5, 0.0), (1.0, 0.0)]);
+        assert_eq!(keyframes.sample(0.49), 1.0);
+        assert_eq!(keyframes.sample(0.5), 0.0);
+        assert_eq!(keyframes.sample(0.51), 0.0);
+    }
+
+    #[test]
+    fn keyframes_are_sorted() {
+        let keyframes = Keyframes::from(vec![(1.0, 0.0), (0.0, 1.0)]);
+        assert_eq!(keyframes.keyframes(), [(0.0, 1.0), (1.0, 0.0)]);
+    }
+
+    #[test]
+    fn curves_clamp_the_age() {
+        assert_eq!(LifetimeCurve::Linear.sample(-1.0), 1.0);
+        assert_eq!(LifetimeCurve::Linear.sample(2.0), 0.0);
+        let curve = LifetimeCurve::keyframes([(0.0, 0.0), (1.0, 2.0)]);
+        assert_eq!(curve.sample(-1.0), 0.0);
+        assert_eq!(curve.sample(2.0), 2.0);
+    }
+}
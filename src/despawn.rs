@@ -1,9 +1,18 @@
 use bevy_asset::{Asset, Handle};
+use bevy_color::LinearRgba;
 use bevy_image::Image;
 use bevy_math::Vec2;
 use bevy_reflect::Reflect;
-use bevy_render::render_resource::{AsBindGroup, ShaderRef};
-use bevy_sprite::{AlphaMode2d, Material2d};
+use bevy_render::{
+    mesh::MeshVertexBufferLayoutRef,
+    render_resource::{
+        AsBindGroup, BlendComponent, BlendFactor, BlendOperation, BlendState,
+        RenderPipelineDescriptor, ShaderRef, SpecializedMeshPipelineError,
+    },
+};
+use bevy_sprite::{AlphaMode2d, Material2d, Material2dKey};
+
+use crate::events::DespawnBlendMode;
 
 // Needed for AsBindGroup derive macro since it expects the bevy crate.
 mod bevy {
@@ -13,6 +22,7 @@ mod bevy {
 }
 
 #[derive(AsBindGroup, Clone, Reflect, Asset)]
+#[bind_group_data(DespawnMaterialKey)]
 pub struct DespawnMaterial {
     #[texture(0)]
     #[sampler(1)]
@@ -37,6 +47,151 @@ pub struct DespawnMaterial {
     /// ensures 16-byte alignment.
     #[uniform(2)]
     pub padding: u32,
+
+    /// Multiplies the color of the underlying texture, applied after grayscaling.
+    #[uniform(2)]
+    pub tint: LinearRgba,
+
+    /// How much of the texture has dissolved, as a value between 0.0 and 1.0
+    #[uniform(2)]
+    pub dissolve: f32,
+
+    /// The width of the edge of the dissolved area, as a value between 0.0 and 1.0
+    #[uniform(2)]
+    pub dissolve_edge_width: f32,
+
+    /// The scale of the noise used to dissolve the texture
+    #[uniform(2)]
+    pub dissolve_noise_scale: f32,
+
+    /// The color of the edge of the dissolved area
+    #[uniform(2)]
+    pub dissolve_edge_color: LinearRgba,
+
+    /// The color to mix in for the flash
+    #[uniform(2)]
+    pub flash_color: LinearRgba,
+
+    /// How much of the flash color to mix in, as a value between 0.0 and 1.0
+    #[uniform(2)]
+    pub flash: f32,
+
+    /// See [DespawnBlendMode::shader_index]
+    #[uniform(2)]
+    pub blend_mode_index: u32,
+
+    /// The threshold for [DespawnBlendMode::Mask]
+    #[uniform(2)]
+    pub alpha_cutoff: f32,
+
+    pub blend_mode: DespawnBlendMode,
+}
+
+impl Default for DespawnMaterial {
+    fn default() -> Self {
+        Self {
+            source_image: None,
+            offset: Vec2::ZERO,
+            size: Vec2::ONE,
+            alpha: 1.0,
+            gray: 0,
+            padding: 0,
+            tint: LinearRgba::WHITE,
+            dissolve: 0.0,
+            dissolve_edge_width: 0.0,
+            dissolve_noise_scale: 0.0,
+            dissolve_edge_color: LinearRgba::NONE,
+            flash_color: LinearRgba::NONE,
+            flash: 0.0,
+            blend_mode_index: 0,
+            alpha_cutoff: 0.0,
+            blend_mode: DespawnBlendMode::Blend,
+        }
+    }
+}
+
+impl DespawnMaterial {
+    pub fn with_blend_mode(mut self, blend_mode: DespawnBlendMode) -> Self {
+        self.blend_mode_index = blend_mode.shader_index();
+        self.alpha_cutoff = match blend_mode {
+            DespawnBlendMode::Mask(threshold) => threshold,
+            _ => 0.0,
+        };
+        self.blend_mode = blend_mode;
+        self
+    }
+}
+
+/// Used to specialize the pipeline of a [DespawnMaterial] by its blend mode.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DespawnMaterialKey {
+    blend_state: Option<BlendState>,
+}
+
+impl From<&DespawnMaterial> for DespawnMaterialKey {
+    fn from(material: &DespawnMaterial) -> Self {
+        Self {
+            blend_state: material.blend_mode.blend_state(),
+        }
+    }
+}
+
+impl DespawnBlendMode {
+    /// The value the shader uses to identify this blend mode, matching the constants in
+    /// despawn_material.wgsl
+    pub(crate) fn shader_index(&self) -> u32 {
+        match self {
+            Self::Blend => 0,
+            Self::Premultiplied => 1,
+            Self::Additive => 2,
+            Self::Multiply => 3,
+            Self::Opaque => 4,
+            Self::Mask(_) => 5,
+        }
+    }
+
+    fn blend_state(&self) -> Option<BlendState> {
+        match self {
+            Self::Blend => Some(BlendState::ALPHA_BLENDING),
+            Self::Premultiplied => Some(BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+            Self::Additive => Some(BlendState {
+                color: BlendComponent {
+                    src_factor: BlendFactor::SrcAlpha,
+                    dst_factor: BlendFactor::One,
+                    operation: BlendOperation::Add,
+                },
+                alpha: BlendComponent {
+                    src_factor: BlendFactor::Zero,
+                    dst_factor: BlendFactor::One,
+                    operation: BlendOperation::Add,
+                },
+            }),
+            // The shader blends the color towards white by the alpha beforehand.
+            Self::Multiply => Some(BlendState {
+                color: BlendComponent {
+                    src_factor: BlendFactor::Dst,
+                    dst_factor: BlendFactor::Zero,
+                    operation: BlendOperation::Add,
+                },
+                alpha: BlendComponent {
+                    src_factor: BlendFactor::Zero,
+                    dst_factor: BlendFactor::One,
+                    operation: BlendOperation::Add,
+                },
+            }),
+            Self::Opaque | Self::Mask(_) => None,
+        }
+    }
+
+    /// The [AlphaMode2d] to use for [ColorMaterial][bevy_sprite::ColorMaterial] particles, which
+    /// don't support every blend mode.
+    pub(crate) fn color_material_alpha_mode(&self) -> AlphaMode2d {
+        match self {
+            Self::Opaque => AlphaMode2d::Opaque,
+            Self::Mask(threshold) => AlphaMode2d::Mask(*threshold),
+            _ => AlphaMode2d::Blend,
+        }
+    }
 }
 
 impl Material2d for DespawnMaterial {
@@ -45,6 +200,23 @@ impl Material2d for DespawnMaterial {
     }
 
     fn alpha_mode(&self) -> AlphaMode2d {
-        AlphaMode2d::Blend
+        match self.blend_mode {
+            DespawnBlendMode::Opaque => AlphaMode2d::Opaque,
+            DespawnBlendMode::Mask(threshold) => AlphaMode2d::Mask(threshold),
+            _ => AlphaMode2d::Blend,
+        }
+    }
+
+    fn specialize(
+        descriptor: &mut RenderPipelineDescriptor,
+        _layout: &MeshVertexBufferLayoutRef,
+        key: Material2dKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        if let Some(fragment) = descriptor.fragment.as_mut() {
+            for target in fragment.targets.iter_mut().flatten() {
+                target.blend = key.bind_group_data.blend_state;
+            }
+        }
+        Ok(())
     }
 }
@@ -8,9 +8,24 @@ struct DespawnMaterial {
     size: vec2<f32>,
     alpha: f32,
     gray: u32,
-    padding: u32
+    padding: u32,
+    tint: vec4<f32>,
+    dissolve: f32,
+    dissolve_edge_width: f32,
+    dissolve_noise_scale: f32,
+    dissolve_edge_color: vec4<f32>,
+    flash_color: vec4<f32>,
+    flash: f32,
+    blend_mode_index: u32,
+    alpha_cutoff: f32,
 };
 
+// Matches DespawnBlendMode::shader_index
+const BLEND_MODE_PREMULTIPLIED: u32 = 1u;
+const BLEND_MODE_MULTIPLY: u32 = 3u;
+const BLEND_MODE_OPAQUE: u32 = 4u;
+const BLEND_MODE_MASK: u32 = 5u;
+
 @group(2) @binding(0)
 var texture: texture_2d<f32>;
 
@@ -20,6 +35,20 @@ var our_sampler: sampler;
 @group(2) @binding(2)
 var<uniform> despawn_material: DespawnMaterial;
 
+fn hash(p: vec2<f32>) -> f32 {
+    return fract(sin(dot(p, vec2<f32>(127.1, 311.7))) * 43758.5453);
+}
+
+fn value_noise(p: vec2<f32>) -> f32 {
+    let i = floor(p);
+    let f = fract(p);
+    let u = f * f * (3.0 - 2.0 * f);
+    return mix(
+        mix(hash(i), hash(i + vec2<f32>(1.0, 0.0)), u.x),
+        mix(hash(i + vec2<f32>(0.0, 1.0)), hash(i + vec2<f32>(1.0, 1.0)), u.x),
+        u.y
+    );
+}
 
 @fragment
 fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
@@ -38,6 +67,43 @@ fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
     }
 
     new_color[3] = despawn_material.alpha * color.a;
+    new_color = new_color * despawn_material.tint;
+    new_color = vec4<f32>(
+        mix(new_color.rgb, despawn_material.flash_color.rgb, despawn_material.flash),
+        new_color.a
+    );
+
+    if(despawn_material.dissolve > 0.0) {
+        // Use the uv of the original sprite so the noise lines up across particles.
+        let noise = value_noise(in.uv * despawn_material.dissolve_noise_scale);
+        // Scale the threshold so the edge has dissolved as well once dissolve reaches 1.0
+        let threshold = despawn_material.dissolve * (1.0 + despawn_material.dissolve_edge_width);
+        if(noise < threshold - despawn_material.dissolve_edge_width) {
+            discard;
+        }
+        if(noise < threshold) {
+            new_color = vec4<f32>(
+                despawn_material.dissolve_edge_color.rgb,
+                new_color.a * despawn_material.dissolve_edge_color.a
+            );
+        }
+    }
+
+    if(despawn_material.blend_mode_index == BLEND_MODE_PREMULTIPLIED) {
+        new_color = vec4<f32>(new_color.rgb * new_color.a, new_color.a);
+    }
+    else if(despawn_material.blend_mode_index == BLEND_MODE_MULTIPLY) {
+        new_color = vec4<f32>(mix(vec3<f32>(1.0), new_color.rgb, new_color.a), new_color.a);
+    }
+    else if(despawn_material.blend_mode_index == BLEND_MODE_OPAQUE) {
+        new_color[3] = 1.0;
+    }
+    else if(despawn_material.blend_mode_index == BLEND_MODE_MASK) {
+        if(new_color.a < despawn_material.alpha_cutoff) {
+            discard;
+        }
+        new_color[3] = 1.0;
+    }
     return new_color;
 
 }
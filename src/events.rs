@@ -1,38 +1,261 @@
 //! Event and related utilities for triggering despawn particles events
-use bevy_ecs::{entity::Entity, event::Event};
+use bevy_ecs::{entity::Entity, event::Event, system::Commands};
 
-use bevy_asset::Handle;
+use bevy_asset::{Asset, Handle};
+use bevy_color::{Color, LinearRgba};
+use bevy_reflect::{Reflect, ReflectDeserialize, ReflectSerialize};
 use bevy_render::mesh::Mesh;
 
 use bevy_math::Vec2;
 
 use bevy_variable_property::Property;
 
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    curve::LifetimeCurve, gradient::ColorGradient, lod::LodPolicy, secondary::SecondaryEmitters,
+    trail::Trail,
+};
+
+use std::{
+    borrow::Cow,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
 impl DespawnParticlesPreset {
     /// Creates an event from the given preset.
     pub fn create_event(&self, entity: Entity) -> DespawnParticlesEvent {
-        DespawnParticlesEvent {
-            entity,
-            angvel: self.angvel.clone(),
-            linvel: self.linvel.clone(),
-            linvel_addtl: self.linvel_addtl.clone(),
-            linear_damping: self.linear_damping.clone(),
-            angular_damping: self.angular_damping.clone(),
-            mass: self.mass.clone(),
-            lifetime: self.lifetime.clone(),
-            ignore_parent_phys: self.ignore_parent_phys.clone(),
-            shrink: self.shrink,
-            fade: self.fade,
-            mesh_override: self.mesh_override.clone(),
-            target_num_particles: self.target_num_particles.clone(),
-            gray: false,
-            recurse: false,
+        self.clone().build(entity)
+    }
+}
+
+/// Identifies the particles generated by a single [DespawnParticlesEvent].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct DespawnEffectId(pub u64);
+
+impl DespawnEffectId {
+    /// Creates a new id that is different from every id previously created this way.
+    pub fn unique() -> Self {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+        Self(NEXT_ID.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+/// Sent, and triggered for observers, once every particle generated by a [DespawnParticlesEvent]
+/// is gone, whether it expired or was removed to stay under
+/// [DespawnParticlesConfig::max_particles][crate::resources::DespawnParticlesConfig::max_particles].
+///
+/// This is also sent when no particles could be generated for the event.
+#[derive(Clone, Copy, Debug, Event)]
+pub struct DespawnParticlesFinished {
+    /// The target entity of the original event. This entity may no longer exist.
+    pub source: Entity,
+
+    /// The id of the original event, see [DespawnParticlesEvent::effect_id].
+    pub effect_id: DespawnEffectId,
+}
+
+/// A callback that is given the particles generated for a [DespawnParticlesEvent], see
+/// [DespawnParticlesEvent::on_spawn].
+#[derive(Clone)]
+pub struct DespawnParticlesCallback(pub Arc<DespawnParticlesCallbackFn>);
+
+/// The function wrapped by a [DespawnParticlesCallback].
+type DespawnParticlesCallbackFn = dyn Fn(&mut Commands, &[Entity]) + Send + Sync;
+
+impl<F: Fn(&mut Commands, &[Entity]) + Send + Sync + 'static> From<F> for DespawnParticlesCallback {
+    fn from(f: F) -> Self {
+        Self(Arc::new(f))
+    }
+}
+
+/// Determines what happens to the target entity of a [DespawnParticlesEvent].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SourceMode {
+    /// The target entity is despawned. This is the default.
+    #[default]
+    Despawn,
+
+    /// The target entity is left untouched, the particles are generated from a snapshot of it.
+    Keep,
+
+    /// The target entity is hidden via its [Visibility][bevy_render::view::Visibility] until the
+    /// generated particles have all expired, after which its previous visibility is restored.
+    HideAndRestore,
+
+    /// The target entity is hidden via its [Visibility][bevy_render::view::Visibility] until the
+    /// generated particles have all expired, after which it is despawned.
+    HideAndDespawn,
+}
+
+/// The space the particles of a [DespawnParticlesEvent] are simulated in, see
+/// [DespawnParticlesEvent::simulation_space].
+///
+/// In a local space the particles are children of another entity, so they move along with it.
+/// That entity should outlive the particles. With the `bevy_rapier2d` feature the particles are
+/// always simulated in world space.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SimulationSpace {
+    /// The particles are top-level entities. This is the default.
+    #[default]
+    World,
+
+    /// The local space of the parent of the target entity, or world space if it has none.
+    SourceParent,
+
+    /// The local space of the given entity. Cannot be used in preset files.
+    #[serde(skip)]
+    Entity(Entity),
+}
+
+/// The point that shrinking particles shrink towards, see [DespawnParticlesEvent::shrink_pivot].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum ShrinkPivot {
+    /// The centroid of the particle. This is the default.
+    #[default]
+    Centroid,
+
+    /// The vertex of the particle that is furthest along its initial direction of travel.
+    LeadingVertex,
+
+    /// An offset from the centroid of the particle, in the local space of the source mesh.
+    Offset(Vec2),
+}
+
+/// How particles are blended with what is behind them, see [DespawnParticlesEvent::blend_mode].
+///
+/// Particles generated from meshes with a [ColorMaterial][bevy_sprite::ColorMaterial] only support
+/// [DespawnBlendMode::Blend], [DespawnBlendMode::Opaque] and [DespawnBlendMode::Mask], other modes
+/// fall back to [DespawnBlendMode::Blend].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Reflect, Serialize, Deserialize)]
+pub enum DespawnBlendMode {
+    /// Standard alpha blending. This is the default.
+    #[default]
+    Blend,
+
+    /// Alpha blending where the color is multiplied by the alpha before blending.
+    Premultiplied,
+
+    /// The color is added to what is behind it, which can be used for glowing particles.
+    Additive,
+
+    /// The color is multiplied with what is behind it.
+    Multiply,
+
+    /// Fully opaque, the alpha is ignored.
+    Opaque,
+
+    /// Fully opaque where the alpha is above the given threshold and fully transparent
+    /// elsewhere. Unlike the blended modes these particles don't need to be sorted.
+    Mask(f32),
+}
+
+/// Makes particles start with a flash of color that decays, see [DespawnParticlesEvent::flash].
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Flash {
+    /// The color of the flash. Values outside of the standard range can be used with an HDR
+    /// camera and bloom to make the particles glow.
+    pub color: Color,
+
+    /// How long the flash takes to decay, in seconds.
+    pub duration: f32,
+}
+
+impl Default for Flash {
+    fn default() -> Self {
+        Self {
+            color: LinearRgba::rgb(4.0, 4.0, 4.0).into(),
+            duration: 0.1,
+        }
+    }
+}
+
+impl Flash {
+    pub fn new<T: Into<Color>>(color: T, duration: f32) -> Self {
+        Self {
+            color: color.into(),
+            duration,
+        }
+    }
+}
+
+/// Makes particles generated from sprites dissolve using noise over their lifetime, see
+/// [DespawnParticlesEvent::dissolve].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Dissolve {
+    /// How much of the particle remains over its lifetime.
+    pub curve: LifetimeCurve,
+
+    /// The color of the edge between the dissolved and remaining parts. Values outside of the
+    /// standard range can be used with an HDR camera to make the edge glow.
+    pub edge_color: Color,
+
+    /// The width of the edge, as a value between 0.0 and 1.0.
+    pub edge_width: f32,
+
+    /// The scale of the noise, where larger values give smaller details.
+    pub noise_scale: f32,
+
+    /// When false, the sprite is not split and instead dissolves as a single particle.
+    pub split: bool,
+}
+
+impl Default for Dissolve {
+    fn default() -> Self {
+        Self {
+            curve: LifetimeCurve::Linear,
+            edge_color: Color::srgb(1.0, 0.5, 0.0),
+            edge_width: 0.05,
+            noise_scale: 8.0,
+            split: true,
         }
     }
 }
 
+impl Dissolve {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// See [Dissolve::curve]
+    pub fn with_curve<T: Into<LifetimeCurve>>(mut self, curve: T) -> Self {
+        self.curve = curve.into();
+        self
+    }
+
+    /// See [Dissolve::edge_color]
+    pub fn with_edge_color<T: Into<Color>>(mut self, edge_color: T) -> Self {
+        self.edge_color = edge_color.into();
+        self
+    }
+
+    /// See [Dissolve::edge_width]
+    pub fn with_edge_width(mut self, edge_width: f32) -> Self {
+        self.edge_width = edge_width;
+        self
+    }
+
+    /// See [Dissolve::noise_scale]
+    pub fn with_noise_scale(mut self, noise_scale: f32) -> Self {
+        self.noise_scale = noise_scale;
+        self
+    }
+
+    /// See [Dissolve::split]
+    pub fn with_split(mut self, split: bool) -> Self {
+        self.split = split;
+        self
+    }
+}
+
 /// Causes the given entity to be despawned and
-/// [DespawnParticles][crate::components::DespawnParticle] to be generated.
+/// [DespawnParticles][crate::components::DespawnParticle] to be generated. The entity can
+/// instead be kept or hidden, see [SourceMode].
 ///
 /// Each of the given properties is applied to each of the generated
 /// [DespawnParticles][crate::components::DespawnParticle].
@@ -41,6 +264,10 @@ pub struct DespawnParticlesEvent {
     /// The target entity
     pub entity: Entity,
 
+    /// The id of this effect, used to identify the matching [DespawnParticlesFinished].
+    /// A unique id is assigned when the event is built.
+    pub effect_id: DespawnEffectId,
+
     /// The angular velocity
     pub angvel: Property<f32>,
 
@@ -66,6 +293,10 @@ pub struct DespawnParticlesEvent {
     /// The mass
     pub mass: Property<f32>,
 
+    /// Added to the z of each particle, relative to the z of the entity. Sampled per particle,
+    /// so a range spreads the particles across that range.
+    pub z_offset: Property<f32>,
+
     /// When true, the generated particles will ignore the target entity's velocities. When false, the
     /// target's velocity is added to each generated particle.
     pub ignore_parent_phys: bool,
@@ -76,37 +307,139 @@ pub struct DespawnParticlesEvent {
     /// When true, generated particles will fade as it's lifetime approaches 0.
     pub fade: bool,
 
+    /// The alpha of fading particles over their lifetime.
+    pub fade_curve: LifetimeCurve,
+
+    /// The scale of shrinking particles over their lifetime, relative to their initial scale.
+    pub shrink_curve: LifetimeCurve,
+
+    /// How much shrinking applies to each axis, where [Vec2::ONE] shrinks uniformly and
+    /// [Vec2::X] only shrinks horizontally.
+    pub shrink_axes: Vec2,
+
+    /// The point that shrinking particles shrink towards.
+    pub shrink_pivot: ShrinkPivot,
+
+    /// When set, multiplies the velocity of the particles over their lifetime.
+    pub velocity_curve: Option<LifetimeCurve>,
+
     /// Use this mesh over the one used by the entity
     pub mesh_override: Option<Handle<Mesh>>,
 
     /// The number of particles to try to match. The actual number may be more than this.
     pub target_num_particles: Property<usize>,
 
+    /// Scales down [target_num_particles][DespawnParticlesEvent::target_num_particles] based on
+    /// how the entity appears to the cameras. When set, overrides
+    /// [DespawnParticlesConfig::lod][crate::resources::DespawnParticlesConfig::lod], use
+    /// [LodPolicy::disabled] to opt out of it.
+    pub lod: Option<LodPolicy>,
+
     /// When true, will grayscale the particles
     pub gray: bool,
 
-    /// When true, despawns the entities children as well.
+    /// Multiplies the color of the particles over their lifetime.
+    pub color_over_lifetime: Option<ColorGradient>,
+
+    /// When set, particles generated from sprites dissolve over their lifetime.
+    pub dissolve: Option<Dissolve>,
+
+    /// How the particles are blended with what is behind them.
+    pub blend_mode: DespawnBlendMode,
+
+    /// When set, the particles start with a flash of color that decays.
+    pub flash: Option<Flash>,
+
+    /// Lightweight particles, such as sparks, dust and smoke, to spawn alongside the fragments.
+    pub secondary_emitters: Option<SecondaryEmitters>,
+
+    /// When set, a fading ribbon trails behind each particle.
+    pub trail: Option<Trail>,
+
+    /// When true, every descendant of the entity with a sprite or mesh generates particles as
+    /// well, as part of the same effect, and the descendants are despawned along with the entity.
     pub recurse: bool,
+
+    /// What to do with the target entity, see [SourceMode].
+    pub source_mode: SourceMode,
+
+    /// The space the particles are simulated in, see [SimulationSpace].
+    pub simulation_space: SimulationSpace,
+
+    /// The name of the [ParticleBudget][crate::resources::ParticleBudget] the particles count
+    /// towards, see [DespawnParticlesConfig::budgets][crate::resources::DespawnParticlesConfig::budgets].
+    /// When not set, or when there is no budget with this name, the particles count towards
+    /// [DespawnParticlesConfig::max_particles][crate::resources::DespawnParticlesConfig::max_particles].
+    pub budget: Option<Cow<'static, str>>,
+
+    /// A user-defined tag, stored on the
+    /// [DespawnParticlesEffectRoot][crate::components::DespawnParticlesEffectRoot] of the
+    /// generated particles.
+    pub tag: Option<Cow<'static, str>>,
+
+    /// Called with the generated particles right after they are spawned, which can be used to
+    /// insert additional components on them.
+    pub on_spawn: Option<DespawnParticlesCallback>,
 }
 
 /// The builder struct for [DespawnParticlesEvent], typically this should be instantiated with
 /// [DespawnParticlesEvent::builder].
-#[derive(Clone)]
+///
+/// Also used as [DespawnParticlesPreset], which can be loaded as an asset, see
+/// [DespawnParticlesPresetLoader][crate::loader::DespawnParticlesPresetLoader].
+#[derive(Clone, Asset, Reflect, Serialize, Deserialize)]
+#[reflect(opaque, Serialize, Deserialize)]
+#[serde(default)]
 pub struct DespawnParticlesEventBuilder {
+    #[serde(with = "crate::property")]
     pub angvel: Property<f32>,
+    #[serde(with = "crate::property")]
     pub linvel: Property<f32>,
+    #[serde(with = "crate::property")]
     pub linvel_addtl: Property<Vec2>,
+    #[serde(with = "crate::property")]
     pub linear_damping: Property<f32>,
+    #[serde(with = "crate::property")]
     pub angular_damping: Property<f32>,
+    #[serde(with = "crate::property")]
     pub lifetime: Property<f32>,
+    #[serde(with = "crate::property")]
     pub mass: Property<f32>,
+    #[serde(with = "crate::property")]
+    pub z_offset: Property<f32>,
     pub ignore_parent_phys: bool,
     pub shrink: bool,
     pub fade: bool,
+    pub fade_curve: LifetimeCurve,
+    pub shrink_curve: LifetimeCurve,
+    pub shrink_axes: Vec2,
+    pub shrink_pivot: ShrinkPivot,
+    pub velocity_curve: Option<LifetimeCurve>,
+    #[serde(skip)]
     pub mesh_override: Option<Handle<Mesh>>,
+    #[serde(with = "crate::property")]
     pub target_num_particles: Property<usize>,
+    pub lod: Option<LodPolicy>,
     pub gray: bool,
+    pub color_over_lifetime: Option<ColorGradient>,
+    pub dissolve: Option<Dissolve>,
+    pub blend_mode: DespawnBlendMode,
+    pub flash: Option<Flash>,
+    pub secondary_emitters: Option<SecondaryEmitters>,
+    pub trail: Option<Trail>,
     pub recurse: bool,
+    pub source_mode: SourceMode,
+    pub simulation_space: SimulationSpace,
+    pub budget: Option<Cow<'static, str>>,
+    pub tag: Option<Cow<'static, str>>,
+    #[serde(skip)]
+    pub on_spawn: Option<DespawnParticlesCallback>,
+}
+
+impl Default for DespawnParticlesEventBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl DespawnParticlesEvent {
@@ -125,13 +458,31 @@ impl DespawnParticlesEventBuilder {
             linear_damping: Default::default(),
             angular_damping: Default::default(),
             mass: Default::default(),
+            z_offset: Default::default(),
             ignore_parent_phys: false,
             shrink: false,
             fade: false,
+            fade_curve: LifetimeCurve::Linear,
+            shrink_curve: LifetimeCurve::Linear,
+            shrink_axes: Vec2::ONE,
+            shrink_pivot: ShrinkPivot::Centroid,
+            velocity_curve: None,
             mesh_override: None,
             target_num_particles: 64.into(),
+            lod: None,
             gray: false,
+            color_over_lifetime: None,
+            dissolve: None,
+            blend_mode: DespawnBlendMode::Blend,
+            flash: None,
+            secondary_emitters: None,
+            trail: None,
             recurse: false,
+            source_mode: SourceMode::Despawn,
+            simulation_space: SimulationSpace::World,
+            budget: None,
+            tag: None,
+            on_spawn: None,
         }
     }
 
@@ -183,6 +534,12 @@ impl DespawnParticlesEventBuilder {
         self
     }
 
+    /// See [DespawnParticlesEvent::z_offset]
+    pub fn with_z_offset<T: Into<Property<f32>>>(mut self, v: T) -> Self {
+        self.z_offset = v.into();
+        self
+    }
+
     /// See [DespawnParticlesEvent::shrink]
     pub fn with_shrink(mut self, shrink: bool) -> Self {
         self.shrink = shrink;
@@ -195,6 +552,36 @@ impl DespawnParticlesEventBuilder {
         self
     }
 
+    /// See [DespawnParticlesEvent::fade_curve]
+    pub fn with_fade_curve<T: Into<LifetimeCurve>>(mut self, fade_curve: T) -> Self {
+        self.fade_curve = fade_curve.into();
+        self
+    }
+
+    /// See [DespawnParticlesEvent::shrink_curve]
+    pub fn with_shrink_curve<T: Into<LifetimeCurve>>(mut self, shrink_curve: T) -> Self {
+        self.shrink_curve = shrink_curve.into();
+        self
+    }
+
+    /// See [DespawnParticlesEvent::shrink_axes]
+    pub fn with_shrink_axes(mut self, shrink_axes: Vec2) -> Self {
+        self.shrink_axes = shrink_axes;
+        self
+    }
+
+    /// See [DespawnParticlesEvent::shrink_pivot]
+    pub fn with_shrink_pivot(mut self, shrink_pivot: ShrinkPivot) -> Self {
+        self.shrink_pivot = shrink_pivot;
+        self
+    }
+
+    /// See [DespawnParticlesEvent::velocity_curve]
+    pub fn with_velocity_curve<T: Into<LifetimeCurve>>(mut self, velocity_curve: T) -> Self {
+        self.velocity_curve = Some(velocity_curve.into());
+        self
+    }
+
     pub fn with_mesh_override(mut self, mesh_override: Handle<Mesh>) -> Self {
         self.mesh_override = Some(mesh_override);
         self
@@ -205,35 +592,129 @@ impl DespawnParticlesEventBuilder {
         self
     }
 
+    /// See [DespawnParticlesEvent::lod]
+    pub fn with_lod(mut self, lod: LodPolicy) -> Self {
+        self.lod = Some(lod);
+        self
+    }
+
     /// See [DespawnParticlesEvent::gray]
     pub fn with_gray(mut self, gray: bool) -> Self {
         self.gray = gray;
         self
     }
 
+    /// See [DespawnParticlesEvent::color_over_lifetime]
+    pub fn with_color_over_lifetime<T: Into<ColorGradient>>(mut self, gradient: T) -> Self {
+        self.color_over_lifetime = Some(gradient.into());
+        self
+    }
+
+    /// See [DespawnParticlesEvent::dissolve]
+    pub fn with_dissolve(mut self, dissolve: Dissolve) -> Self {
+        self.dissolve = Some(dissolve);
+        self
+    }
+
+    /// See [DespawnParticlesEvent::blend_mode]
+    pub fn with_blend_mode(mut self, blend_mode: DespawnBlendMode) -> Self {
+        self.blend_mode = blend_mode;
+        self
+    }
+
+    /// See [DespawnParticlesEvent::flash]
+    pub fn with_flash(mut self, flash: Flash) -> Self {
+        self.flash = Some(flash);
+        self
+    }
+
+    /// See [DespawnParticlesEvent::secondary_emitters]
+    pub fn with_secondary_emitters(mut self, secondary_emitters: SecondaryEmitters) -> Self {
+        self.secondary_emitters = Some(secondary_emitters);
+        self
+    }
+
+    /// See [DespawnParticlesEvent::trail]
+    pub fn with_trail(mut self, trail: Trail) -> Self {
+        self.trail = Some(trail);
+        self
+    }
+
     /// See [DespawnParticlesEvent::recurse]
     pub fn with_recurse(mut self, recurse: bool) -> Self {
         self.recurse = recurse;
         self
     }
 
+    /// See [DespawnParticlesEvent::source_mode]
+    pub fn with_source_mode(mut self, source_mode: SourceMode) -> Self {
+        self.source_mode = source_mode;
+        self
+    }
+
+    /// See [DespawnParticlesEvent::simulation_space]
+    pub fn with_simulation_space(mut self, simulation_space: SimulationSpace) -> Self {
+        self.simulation_space = simulation_space;
+        self
+    }
+
+    /// See [DespawnParticlesEvent::budget]
+    pub fn with_budget<T: Into<Cow<'static, str>>>(mut self, budget: T) -> Self {
+        self.budget = Some(budget.into());
+        self
+    }
+
+    /// See [DespawnParticlesEvent::tag]
+    pub fn with_tag<T: Into<Cow<'static, str>>>(mut self, tag: T) -> Self {
+        self.tag = Some(tag.into());
+        self
+    }
+
+    /// See [DespawnParticlesEvent::on_spawn]
+    pub fn with_on_spawn<F: Fn(&mut Commands, &[Entity]) + Send + Sync + 'static>(
+        mut self,
+        on_spawn: F,
+    ) -> Self {
+        self.on_spawn = Some(on_spawn.into());
+        self
+    }
+
     pub fn build(self, entity: Entity) -> DespawnParticlesEvent {
         DespawnParticlesEvent {
             entity,
+            effect_id: DespawnEffectId::unique(),
             angvel: self.angvel,
             linvel: self.linvel,
             linvel_addtl: self.linvel_addtl,
             linear_damping: self.linear_damping,
             angular_damping: self.angular_damping,
             mass: self.mass,
+            z_offset: self.z_offset,
             lifetime: self.lifetime,
             ignore_parent_phys: self.ignore_parent_phys,
             shrink: self.shrink,
             fade: self.fade,
+            fade_curve: self.fade_curve,
+            shrink_curve: self.shrink_curve,
+            shrink_axes: self.shrink_axes,
+            shrink_pivot: self.shrink_pivot,
+            velocity_curve: self.velocity_curve,
             mesh_override: self.mesh_override,
             target_num_particles: self.target_num_particles,
+            lod: self.lod,
             gray: self.gray,
+            color_over_lifetime: self.color_over_lifetime,
+            dissolve: self.dissolve,
+            blend_mode: self.blend_mode,
+            flash: self.flash,
+            secondary_emitters: self.secondary_emitters,
+            trail: self.trail,
             recurse: self.recurse,
+            source_mode: self.source_mode,
+            simulation_space: self.simulation_space,
+            budget: self.budget,
+            tag: self.tag,
+            on_spawn: self.on_spawn,
         }
     }
 }
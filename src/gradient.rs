@@ -0,0 +1,139 @@
+//! Colors that change over the lifetime of a particle.
+use bevy_color::{Color, ColorToComponents, LinearRgba, Mix};
+use serde::{Deserialize, Serialize};
+
+/// A gradient sampled over the normalized lifetime of a particle, where 0.0 is when the particle
+/// is spawned and 1.0 is when it expires. The sampled color multiplies the color of the particle.
+///
+/// ```
+/// # use bevy_color::Color;
+/// # use bevy_despawn_particles::gradient::ColorGradient;
+/// // White-hot, to orange, to dark smoke.
+/// let gradient = ColorGradient::new()
+///     .with_stop(0.0, Color::WHITE)
+///     .with_stop(0.3, Color::srgb(1.0, 0.5, 0.0))
+///     .with_stop(1.0, Color::srgba(0.2, 0.2, 0.2, 0.5));
+/// ```
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(from = "Vec<(f32, Color)>", into = "Vec<(f32, Color)>")]
+pub struct ColorGradient {
+    /// The stops of the gradient, sorted by their position.
+    stops: Vec<(f32, Color)>,
+}
+
+impl ColorGradient {
+    /// Creates an empty gradient, which samples as white.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a stop at the given position, between 0.0 and 1.0.
+    pub fn with_stop<C: Into<Color>>(mut self, position: f32, color: C) -> Self {
+        let idx = self
+            .stops
+            .partition_point(|(stop_position, _)| *stop_position <= position);
+        self.stops.insert(idx, (position, color.into()));
+        self
+    }
+
+    /// The stops of the gradient, sorted by their position.
+    pub fn stops(&self) -> &[(f32, Color)] {
+        &self.stops
+    }
+
+    /// Samples the gradient at the given position, blending the surrounding stops in linear
+    /// space. Positions outside of the stops take the color of the closest stop.
+    pub fn sample(&self, position: f32) -> LinearRgba {
+        let idx = self
+            .stops
+            .partition_point(|(stop_position, _)| *stop_position <= position);
+        match (
+            idx.checked_sub(1).and_then(|idx| self.stops.get(idx)),
+            self.stops.get(idx),
+        ) {
+            (Some((start_position, start)), Some((end_position, end))) => {
+                let t = (position - start_position) / (end_position - start_position);
+                start.to_linear().mix(&end.to_linear(), t)
+            }
+            (Some((_, color)), None) | (None, Some((_, color))) => color.to_linear(),
+            (None, None) => LinearRgba::WHITE,
+        }
+    }
+}
+
+impl From<Vec<(f32, Color)>> for ColorGradient {
+    fn from(mut stops: Vec<(f32, Color)>) -> Self {
+        stops.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+        Self { stops }
+    }
+}
+
+impl From<ColorGradient> for Vec<(f32, Color)> {
+    fn from(gradient: ColorGradient) -> Self {
+        gradient.stops
+    }
+}
+
+impl<C: Into<Color>, const N: usize> From<[(f32, C); N]> for ColorGradient {
+    fn from(stops: [(f32, C); N]) -> Self {
+        stops
+            .into_iter()
+            .fold(Self::new(), |gradient, (position, color)| {
+                gradient.with_stop(position, color)
+            })
+    }
+}
+
+/// Multiplies two colors together, component-wise.
+pub(crate) fn multiply(a: LinearRgba, b: LinearRgba) -> LinearRgba {
+    LinearRgba::from_vec4(a.to_vec4() * b.to_vec4())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_gradient_samples_white() {
+        assert_eq!(ColorGradient::new().sample(0.5), LinearRgba::WHITE);
+    }
+
+    #[test]
+    fn blends_between_stops() {
+        let gradient = ColorGradient::from([(0.0, LinearRgba::RED), (1.0, LinearRgba::BLUE)]);
+        assert_eq!(gradient.sample(0.0), LinearRgba::RED);
+        assert_eq!(gradient.sample(0.5), LinearRgba::new(0.5, 0.0, 0.5, 1.0));
+        assert_eq!(gradient.sample(1.0), LinearRgba::BLUE);
+    }
+
+    #[test]
+    fn out_of_range_takes_the_closest_stop() {
+        let gradient = ColorGradient::from([(0.25, LinearRgba::RED), (0.75, LinearRgba::BLUE)]);
+        assert_eq!(gradient.sample(0.0), LinearRgba::RED);
+        assert_eq!(gradient.sample(-1.0), LinearRgba::RED);
+        assert_eq!(gradient.sample(1.0), LinearRgba::BLUE);
+        assert_eq!(gradient.sample(2.0), LinearRgba::BLUE);
+    }
+
+    #[test]
+    fn duplicate_stops_make_a_hard_edge() {
+        let gradient = ColorGradient::new()
+            .with_stop(0.0, LinearRgba::RED)
+            .with_stop(0.5, LinearRgba::RED)
+            .with_stop(0.5, LinearRgba::BLUE)
+            .with_stop(1.0, LinearRgba::BLUE);
+        assert_eq!(gradient.sample(0.49), LinearRgba::RED);
+        assert_eq!(gradient.sample(0.5), LinearRgba::BLUE);
+        assert_eq!(gradient.sample(0.51), LinearRgba::BLUE);
+    }
+
+    #[test]
+    fn stops_are_sorted() {
+        let gradient = ColorGradient::from(vec![
+            (1.0, Color::from(LinearRgba::BLUE)),
+            (0.0, Color::from(LinearRgba::RED)),
+        ]);
+        assert_eq!(gradient.stops()[0].0, 0.0);
+        assert_eq!(gradient.sample(0.0), LinearRgba::RED);
+    }
+}
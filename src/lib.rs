@@ -7,23 +7,47 @@ use bevy_sprite::Material2dPlugin;
 #[cfg(feature = "bevy_rapier2d")]
 use bevy_rapier2d::prelude::*;
 
+pub mod adaptive;
+pub mod commands;
 pub mod components;
+pub mod curve;
 mod despawn;
 pub mod events;
+pub mod gradient;
+pub mod loader;
+pub mod lod;
+pub mod overrides;
+mod pool;
+mod property;
+pub mod registry;
 pub mod resources;
+pub mod secondary;
 mod systems;
+pub mod trail;
 
 #[cfg(not(feature = "bevy_rapier2d"))]
 pub mod phys;
 
 mod utils;
 
+use adaptive::{update_adaptive_quality, AdaptiveQuality, AdaptiveQualityState};
+use bevy_asset::AssetApp;
+use bevy_diagnostic::{Diagnostic, RegisterDiagnostic};
 use despawn::DespawnMaterial;
-use events::DespawnParticlesEvent;
-use resources::{DespawnParticleQueue, DespawnParticlesConfig};
+use events::{DespawnParticlesEvent, DespawnParticlesFinished, DespawnParticlesPreset};
+use loader::DespawnParticlesPresetLoader;
+use pool::{setup_pool, ParticlePool};
+use registry::{sync_preset_registry, DespawnPresetRegistry};
+use resources::{
+    ActiveDespawnEffects, DespawnParticleQueue, DespawnParticlesConfig, ParticleBudgetQueues,
+    SecondaryParticleQueue,
+};
+use secondary::{emit_dust, emit_smoke, handle_secondary_particles, max_secondary_particles_check};
 use systems::{
-    handle_despawn_particle, handle_despawn_particles_events, max_particles_check, setup,
+    handle_despawn_particle, handle_despawn_particles_events, handle_hidden_despawn_sources,
+    max_particles_check, on_despawn_particle_effect_removed, on_shatter_on_despawn_removed, setup,
 };
+use trail::{on_fragment_trail_removed, setup_trails, update_trails, TrailMaterial};
 
 use std::path::{Path, PathBuf};
 
@@ -51,8 +75,18 @@ impl Plugin for DespawnParticlesPlugin {
         app.add_plugins(Material2dPlugin::<DespawnMaterial>::default())
             .register_type::<DespawnMaterial>();
 
+        // Register presets as an asset
+        app.init_asset::<DespawnParticlesPreset>()
+            .init_asset_loader::<DespawnParticlesPresetLoader>()
+            .register_type::<DespawnParticlesPreset>();
+
         // Register events
         app.add_event::<DespawnParticlesEvent>();
+        app.add_event::<DespawnParticlesFinished>();
+
+        app.add_observer(on_despawn_particle_effect_removed);
+        app.add_observer(on_shatter_on_despawn_removed);
+        app.add_observer(on_fragment_trail_removed);
 
         // Register systems and systemset
         // TODO: These might need to be ordered to prevent conflicts potentially?
@@ -61,11 +95,47 @@ impl Plugin for DespawnParticlesPlugin {
             Update,
             handle_despawn_particles_events.in_set(DespawnParticlesSet),
         );
+        app.add_systems(
+            Update,
+            update_adaptive_quality
+                .in_set(DespawnParticlesSet)
+                .before(handle_despawn_particles_events),
+        );
         app.add_systems(Update, max_particles_check.in_set(DespawnParticlesSet));
-        app.add_systems(Startup, setup);
+        app.add_systems(
+            Update,
+            handle_hidden_despawn_sources.in_set(DespawnParticlesSet),
+        );
+        app.add_systems(
+            Update,
+            sync_preset_registry
+                .in_set(DespawnParticlesSet)
+                .before(handle_despawn_particles_events),
+        );
+        app.add_systems(
+            Update,
+            (
+                handle_secondary_particles,
+                emit_dust,
+                emit_smoke,
+                max_secondary_particles_check,
+            )
+                .in_set(DespawnParticlesSet),
+        );
+        app.add_systems(Update, update_trails.in_set(DespawnParticlesSet));
+        app.add_systems(Startup, (setup, setup_trails, setup_pool));
 
         app.init_resource::<DespawnParticlesConfig>();
         app.init_resource::<DespawnParticleQueue>();
+        app.init_resource::<ParticleBudgetQueues>();
+        app.init_resource::<ParticlePool>();
+        app.init_resource::<SecondaryParticleQueue>();
+        app.init_resource::<TrailMaterial>();
+        app.init_resource::<ActiveDespawnEffects>();
+        app.init_resource::<AdaptiveQualityState>();
+        app.register_diagnostic(Diagnostic::new(AdaptiveQuality::QUALITY))
+            .register_diagnostic(Diagnostic::new(AdaptiveQuality::DEFERRED_EVENTS));
+        app.init_resource::<DespawnPresetRegistry>();
 
         #[cfg(not(feature = "bevy_rapier2d"))]
         {
@@ -81,8 +151,23 @@ impl Plugin for DespawnParticlesPlugin {
 }
 
 pub mod prelude {
-    pub use crate::components::{DespawnMeshOverride, DespawnParticle};
-    pub use crate::events::{DespawnParticlesEvent, DespawnParticlesPreset};
-    pub use crate::resources::DespawnParticlesConfig;
+    pub use crate::adaptive::AdaptiveQuality;
+    pub use crate::commands::{DespawnParticlesCommandsExt, DespawnParticlesEntityCommandsExt};
+    pub use crate::components::{
+        DespawnMeshOverride, DespawnParticle, DespawnParticleEffect, DespawnParticlesEffectRoot,
+        ShatterOnDespawn,
+    };
+    pub use crate::curve::LifetimeCurve;
+    pub use crate::events::{
+        DespawnBlendMode, DespawnEffectId, DespawnParticlesEvent, DespawnParticlesFinished,
+        DespawnParticlesPreset, Dissolve, Flash, ShrinkPivot, SimulationSpace, SourceMode,
+    };
+    pub use crate::gradient::ColorGradient;
+    pub use crate::lod::{LodPolicy, OffscreenLod};
+    pub use crate::overrides::DespawnParticlesPresetOverrides;
+    pub use crate::registry::{DespawnPresetKey, DespawnPresetRegistry};
+    pub use crate::resources::{DespawnParticlesConfig, EvictionPolicy, ParticleBudget};
+    pub use crate::secondary::{Dust, SecondaryEmitters, SecondaryParticleStyle, Smoke, Sparks};
+    pub use crate::trail::Trail;
     pub use crate::{DespawnParticlesPlugin, DespawnParticlesSet};
 }
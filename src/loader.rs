@@ -0,0 +1,168 @@
+//! [AssetLoader] for [DespawnParticlesPreset]s stored as RON.
+use bevy_asset::{
+    io::Reader, AssetLoader, AssetPath, LoadContext, LoadDirectError, ParseAssetPathError,
+};
+use ron::extensions::Extensions;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{events::DespawnParticlesPreset, overrides::DespawnParticlesPresetOverrides};
+
+/// Loads [DespawnParticlesPreset]s from `.despawn.ron` files. Registered by the
+/// [DespawnParticlesPlugin][crate::DespawnParticlesPlugin].
+///
+/// A preset file holds [DespawnParticlesPresetOverrides], so every field is optional. They are
+/// applied on top of the `base` preset file if one is given, otherwise on top of
+/// [DespawnParticlesPreset::new]. The mesh override and spawn callback cannot be set from a file.
+///
+/// ```ron
+/// (
+///     linvel: RandomRange(start: 150.0, end: 300.0),
+///     angvel: RandomChoice([-5.0, -2.5, 2.5, 5.0]),
+///     lifetime: Static(0.8),
+///     fade: true,
+/// )
+/// ```
+///
+/// ```ron
+/// (
+///     base: "explosion.despawn.ron",
+///     linvel_scale: 2.0,
+///     lifetime_scale: 0.5,
+/// )
+/// ```
+///
+/// A preset cannot be its own base, directly or through other presets.
+#[derive(Default)]
+pub struct DespawnParticlesPresetLoader;
+
+/// The settings of [DespawnParticlesPresetLoader].
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct DespawnParticlesPresetLoaderSettings {
+    /// The paths of the presets that are loading this one as their base, used to detect cycles.
+    /// Set by the loader itself when loading a base.
+    pub base_chain: Vec<String>,
+}
+
+#[derive(Error, Debug)]
+pub enum DespawnParticlesPresetLoaderError {
+    #[error("Could not read the preset: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Could not parse the preset: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+
+    #[error("Invalid base preset path: {0}")]
+    InvalidBasePath(#[from] ParseAssetPathError),
+
+    #[error("Could not load the base preset: {0}")]
+    Base(#[from] LoadDirectError),
+
+    #[error("The base preset {0} is already being loaded as a base of this preset")]
+    BaseCycle(String),
+}
+
+impl AssetLoader for DespawnParticlesPresetLoader {
+    type Asset = DespawnParticlesPreset;
+    type Settings = DespawnParticlesPresetLoaderSettings;
+    type Error = DespawnParticlesPresetLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        settings: &DespawnParticlesPresetLoaderSettings,
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        let overrides: DespawnParticlesPresetOverrides = ron::Options::default()
+            .with_default_extension(Extensions::IMPLICIT_SOME)
+            .from_bytes(&bytes)?;
+
+        let base = if let Some(base) = overrides.base.as_ref() {
+            let path = load_context.asset_path().resolve_embed(base)?;
+            let base_chain = base_chain(settings, load_context.asset_path(), &path)
+                .map_err(DespawnParticlesPresetLoaderError::BaseCycle)?;
+            // Loading it this way also reloads this preset whenever the base changes.
+            load_context
+                .loader()
+                .with_settings(move |settings: &mut DespawnParticlesPresetLoaderSettings| {
+                    settings.base_chain = base_chain.clone();
+                })
+                .immediate()
+                .load::<DespawnParticlesPreset>(path)
+                .await?
+                .take()
+        } else {
+            DespawnParticlesPreset::new()
+        };
+
+        Ok(base.with_overrides(&overrides))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["despawn.ron"]
+    }
+}
+
+/// The chain of presets to pass on when loading `base` as the base of the preset at `path`, or
+/// the path of `base` when it is already part of the chain.
+fn base_chain(
+    settings: &DespawnParticlesPresetLoaderSettings,
+    path: &AssetPath,
+    base: &AssetPath,
+) -> Result<Vec<String>, String> {
+    let mut base_chain = settings.base_chain.clone();
+    base_chain.push(path.to_string());
+    let base = base.to_string();
+    if base_chain.contains(&base) {
+        return Err(base);
+    }
+    Ok(base_chain)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(base_chain: &[&str]) -> DespawnParticlesPresetLoaderSettings {
+        DespawnParticlesPresetLoaderSettings {
+            base_chain: base_chain.iter().map(|path| path.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn base_chain_grows_with_each_base() {
+        let base_chain = base_chain(
+            &settings(&["a.despawn.ron"]),
+            &AssetPath::from("b.despawn.ron"),
+            &AssetPath::from("c.despawn.ron"),
+        )
+        .unwrap();
+        assert_eq!(base_chain, ["a.despawn.ron", "b.despawn.ron"]);
+    }
+
+    #[test]
+    fn preset_cannot_be_its_own_base() {
+        assert!(matches!(
+            base_chain(
+                &settings(&[]),
+                &AssetPath::from("a.despawn.ron"),
+                &AssetPath::from("a.despawn.ron"),
+            ),
+            Err(path) if path == "a.despawn.ron"
+        ));
+    }
+
+    #[test]
+    fn base_cycles_are_detected() {
+        assert!(matches!(
+            base_chain(
+                &settings(&["a.despawn.ron", "b.despawn.ron"]),
+                &AssetPath::from("c.despawn.ron"),
+                &AssetPath::from("a.despawn.ron"),
+            ),
+            Err(path) if path == "a.despawn.ron"
+        ));
+    }
+}
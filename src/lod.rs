@@ -0,0 +1,175 @@
+//! Scales down the number of particles of effects that are small on screen, far away from the
+//! cameras or off-screen.
+use bevy_ecs::system::Query;
+use bevy_math::{Rect, Vec2, Vec3};
+use bevy_render::{camera::Camera, primitives::Aabb};
+use bevy_transform::components::GlobalTransform;
+use serde::{Deserialize, Serialize};
+
+/// Scales down [DespawnParticlesEvent::target_num_particles][crate::events::DespawnParticlesEvent::target_num_particles]
+/// based on how the entity appears to the active cameras, see
+/// [DespawnParticlesConfig::lod][crate::resources::DespawnParticlesConfig::lod] and
+/// [DespawnParticlesEvent::lod][crate::events::DespawnParticlesEvent::lod].
+///
+/// When the entity is seen by more than one camera, the camera it appears most detailed in is
+/// used.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LodPolicy {
+    /// Entities smaller than this on screen, in logical pixels along their larger side, get
+    /// proportionally fewer particles. When zero, the size on screen is ignored.
+    pub full_detail_size: f32,
+
+    /// Entities further than this from the camera, in world units, get proportionally fewer
+    /// particles. When not set, the distance is ignored.
+    pub full_detail_distance: Option<f32>,
+
+    /// The fewest particles an entity is scaled down to.
+    pub min_particles: usize,
+
+    /// What happens to entities that are not seen by any camera, see [OffscreenLod].
+    pub offscreen: OffscreenLod,
+}
+
+impl Default for LodPolicy {
+    fn default() -> Self {
+        Self {
+            full_detail_size: 128.0,
+            full_detail_distance: None,
+            min_particles: 4,
+            offscreen: OffscreenLod::Skip,
+        }
+    }
+}
+
+impl LodPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A policy that never scales down the number of particles, which can be used to opt an
+    /// event out of [DespawnParticlesConfig::lod][crate::resources::DespawnParticlesConfig::lod].
+    pub fn disabled() -> Self {
+        Self {
+            full_detail_size: 0.0,
+            full_detail_distance: None,
+            min_particles: 0,
+            offscreen: OffscreenLod::Full,
+        }
+    }
+
+    /// See [LodPolicy::full_detail_size]
+    pub fn with_full_detail_size(mut self, full_detail_size: f32) -> Self {
+        self.full_detail_size = full_detail_size;
+        self
+    }
+
+    /// See [LodPolicy::full_detail_distance]
+    pub fn with_full_detail_distance(mut self, full_detail_distance: f32) -> Self {
+        self.full_detail_distance = Some(full_detail_distance);
+        self
+    }
+
+    /// See [LodPolicy::min_particles]
+    pub fn with_min_particles(mut self, min_particles: usize) -> Self {
+        self.min_particles = min_particles;
+        self
+    }
+
+    /// See [LodPolicy::offscreen]
+    pub fn with_offscreen(mut self, offscreen: OffscreenLod) -> Self {
+        self.offscreen = offscreen;
+        self
+    }
+
+    /// The number of particles to generate for a mesh with the given bounds, or None when the
+    /// particles should be skipped entirely. `scale` is applied to the bounds before the
+    /// transform, for sprites with a custom size.
+    pub(crate) fn target_num_particles(
+        &self,
+        target_num_particles: usize,
+        aabb: Aabb,
+        scale: Vec2,
+        transform: &GlobalTransform,
+        cameras: &Query<(&Camera, &GlobalTransform)>,
+    ) -> Option<usize> {
+        let center = Vec3::from(aabb.center);
+        let half_extents = Vec3::from(aabb.half_extents);
+        let corners = [
+            Vec3::new(-1.0, -1.0, 0.0),
+            Vec3::new(1.0, -1.0, 0.0),
+            Vec3::new(1.0, 1.0, 0.0),
+            Vec3::new(-1.0, 1.0, 0.0),
+        ]
+        .map(|corner| {
+            transform.transform_point((center + corner * half_extents) * scale.extend(1.0))
+        });
+
+        let mut active_cameras = 0;
+        let detail = cameras
+            .iter()
+            .filter(|(camera, _)| camera.is_active)
+            .filter_map(|(camera, camera_transform)| {
+                active_cameras += 1;
+                let viewport_size = camera.logical_viewport_size()?;
+                let mut bounds = Rect::EMPTY;
+                for corner in corners {
+                    let position = camera.world_to_viewport(camera_transform, corner).ok()?;
+                    bounds = bounds.union_point(position);
+                }
+                if bounds
+                    .intersect(Rect::from_corners(Vec2::ZERO, viewport_size))
+                    .is_empty()
+                {
+                    return None;
+                }
+
+                let size = bounds.width().max(bounds.height());
+                let size_detail = if self.full_detail_size > 0.0 {
+                    size / self.full_detail_size
+                } else {
+                    1.0
+                };
+                let distance_detail = self
+                    .full_detail_distance
+                    .map(|full_detail_distance| {
+                        let distance = camera_transform
+                            .translation()
+                            .truncate()
+                            .distance(transform.translation().truncate());
+                        full_detail_distance / distance
+                    })
+                    .unwrap_or(1.0);
+                Some(size_detail.min(distance_detail).min(1.0))
+            })
+            .reduce(f32::max);
+
+        let detail = match (detail, self.offscreen) {
+            (Some(detail), _) => detail,
+            // Without any cameras there is nothing to base the detail on.
+            (None, _) if active_cameras == 0 => 1.0,
+            (None, OffscreenLod::Full) => 1.0,
+            (None, OffscreenLod::Minimum) => 0.0,
+            (None, OffscreenLod::Skip) => return None,
+        };
+        Some(
+            ((target_num_particles as f32 * detail).round() as usize)
+                .max(self.min_particles)
+                .min(target_num_particles),
+        )
+    }
+}
+
+/// What happens to entities that are not seen by any camera, see [LodPolicy::offscreen].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OffscreenLod {
+    /// The entity gets the full number of particles, as if it was on screen.
+    Full,
+
+    /// The entity gets [LodPolicy::min_particles].
+    Minimum,
+
+    /// No particles are generated for the entity. This is the default.
+    #[default]
+    Skip,
+}
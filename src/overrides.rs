@@ -0,0 +1,296 @@
+//! Partial presets, used to build presets on top of one another.
+use std::borrow::Cow;
+
+use bevy_math::Vec2;
+use bevy_variable_property::Property;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    curve::LifetimeCurve,
+    events::{
+        DespawnBlendMode, DespawnParticlesPreset, Dissolve, Flash, ShrinkPivot, SimulationSpace,
+        SourceMode,
+    },
+    gradient::ColorGradient,
+    lod::LodPolicy,
+    property,
+    secondary::SecondaryEmitters,
+    trail::Trail,
+};
+
+/// A set of changes to apply on top of a [DespawnParticlesPreset], see
+/// [DespawnParticlesPreset::with_overrides]. Fields left as None are taken from the preset.
+///
+/// This is also the format of preset files, see
+/// [DespawnParticlesPresetLoader][crate::loader::DespawnParticlesPresetLoader].
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DespawnParticlesPresetOverrides {
+    /// The path of a preset asset to build on top of. Only used when loading a preset file,
+    /// relative paths are relative to the directory of that file.
+    pub base: Option<String>,
+    #[serde(with = "property::option")]
+    pub angvel: Option<Property<f32>>,
+    #[serde(with = "property::option")]
+    pub linvel: Option<Property<f32>>,
+    #[serde(with = "property::option")]
+    pub linvel_addtl: Option<Property<Vec2>>,
+    #[serde(with = "property::option")]
+    pub linear_damping: Option<Property<f32>>,
+    #[serde(with = "property::option")]
+    pub angular_damping: Option<Property<f32>>,
+    #[serde(with = "property::option")]
+    pub lifetime: Option<Property<f32>>,
+    #[serde(with = "property::option")]
+    pub mass: Option<Property<f32>>,
+    #[serde(with = "property::option")]
+    pub z_offset: Option<Property<f32>>,
+    pub ignore_parent_phys: Option<bool>,
+    pub shrink: Option<bool>,
+    pub fade: Option<bool>,
+    pub fade_curve: Option<LifetimeCurve>,
+    pub shrink_curve: Option<LifetimeCurve>,
+    pub shrink_axes: Option<Vec2>,
+    pub shrink_pivot: Option<ShrinkPivot>,
+    pub velocity_curve: Option<LifetimeCurve>,
+    #[serde(with = "property::option")]
+    pub target_num_particles: Option<Property<usize>>,
+    pub lod: Option<LodPolicy>,
+    pub gray: Option<bool>,
+    pub color_over_lifetime: Option<ColorGradient>,
+    pub dissolve: Option<Dissolve>,
+    pub blend_mode: Option<DespawnBlendMode>,
+    pub flash: Option<Flash>,
+    pub secondary_emitters: Option<SecondaryEmitters>,
+    pub trail: Option<Trail>,
+    pub recurse: Option<bool>,
+    pub source_mode: Option<SourceMode>,
+    pub simulation_space: Option<SimulationSpace>,
+    pub budget: Option<Cow<'static, str>>,
+    pub tag: Option<Cow<'static, str>>,
+
+    /// Multiplies the linear velocity, applied after the other fields.
+    pub linvel_scale: Option<f32>,
+
+    /// Multiplies the angular velocity, applied after the other fields.
+    pub angvel_scale: Option<f32>,
+
+    /// Multiplies the lifetime, applied after the other fields.
+    pub lifetime_scale: Option<f32>,
+}
+
+impl DespawnParticlesPresetOverrides {
+    /// Combines these overrides with the given ones, which take precedence. Scales are
+    /// multiplied together.
+    pub fn merge(self, other: &Self) -> Self {
+        fn scale(a: Option<f32>, b: Option<f32>) -> Option<f32> {
+            match (a, b) {
+                (Some(a), Some(b)) => Some(a * b),
+                (a, b) => a.or(b),
+            }
+        }
+        Self {
+            base: other.base.clone().or(self.base),
+            angvel: other.angvel.clone().or(self.angvel),
+            linvel: other.linvel.clone().or(self.linvel),
+            linvel_addtl: other.linvel_addtl.clone().or(self.linvel_addtl),
+            linear_damping: other.linear_damping.clone().or(self.linear_damping),
+            angular_damping: other.angular_damping.clone().or(self.angular_damping),
+            lifetime: other.lifetime.clone().or(self.lifetime),
+            mass: other.mass.clone().or(self.mass),
+            z_offset: other.z_offset.clone().or(self.z_offset),
+            ignore_parent_phys: other.ignore_parent_phys.or(self.ignore_parent_phys),
+            shrink: other.shrink.or(self.shrink),
+            fade: other.fade.or(self.fade),
+            fade_curve: other.fade_curve.clone().or(self.fade_curve),
+            shrink_curve: other.shrink_curve.clone().or(self.shrink_curve),
+            shrink_axes: other.shrink_axes.or(self.shrink_axes),
+            shrink_pivot: other.shrink_pivot.or(self.shrink_pivot),
+            velocity_curve: other.velocity_curve.clone().or(self.velocity_curve),
+            target_num_particles: other
+                .target_num_particles
+                .clone()
+                .or(self.target_num_particles),
+            lod: other.lod.or(self.lod),
+            gray: other.gray.or(self.gray),
+            color_over_lifetime: other
+                .color_over_lifetime
+                .clone()
+                .or(self.color_over_lifetime),
+            dissolve: other.dissolve.clone().or(self.dissolve),
+            blend_mode: other.blend_mode.or(self.blend_mode),
+            flash: other.flash.or(self.flash),
+            secondary_emitters: other.secondary_emitters.clone().or(self.secondary_emitters),
+            trail: other.trail.or(self.trail),
+            recurse: other.recurse.or(self.recurse),
+            source_mode: other.source_mode.or(self.source_mode),
+            simulation_space: other.simulation_space.or(self.simulation_space),
+            budget: other.budget.clone().or(self.budget),
+            tag: other.tag.clone().or(self.tag),
+            linvel_scale: scale(self.linvel_scale, other.linvel_scale),
+            angvel_scale: scale(self.angvel_scale, other.angvel_scale),
+            lifetime_scale: scale(self.lifetime_scale, other.lifetime_scale),
+        }
+    }
+}
+
+impl DespawnParticlesPreset {
+    /// Applies the given overrides on top of this preset. The
+    /// [base][DespawnParticlesPresetOverrides::base] is ignored.
+    pub fn with_overrides(mut self, overrides: &DespawnParticlesPresetOverrides) -> Self {
+        macro_rules! apply {
+            ($($field:ident),*) => {
+                $(
+                    if let Some(v) = overrides.$field.as_ref() {
+                        self.$field = v.clone();
+                    }
+                )*
+            };
+        }
+        apply!(
+            angvel,
+            linvel,
+            linvel_addtl,
+            linear_damping,
+            angular_damping,
+            lifetime,
+            mass,
+            z_offset,
+            ignore_parent_phys,
+            shrink,
+            fade,
+            fade_curve,
+            shrink_curve,
+            shrink_axes,
+            shrink_pivot,
+            target_num_particles,
+            gray,
+            blend_mode,
+            recurse,
+            source_mode,
+            simulation_space
+        );
+        if let Some(tag) = overrides.tag.as_ref() {
+            self.tag = Some(tag.clone());
+        }
+        if let Some(budget) = overrides.budget.as_ref() {
+            self.budget = Some(budget.clone());
+        }
+        if let Some(curve) = overrides.velocity_curve.as_ref() {
+            self.velocity_curve = Some(curve.clone());
+        }
+        if let Some(gradient) = overrides.color_over_lifetime.as_ref() {
+            self.color_over_lifetime = Some(gradient.clone());
+        }
+        if let Some(dissolve) = overrides.dissolve.as_ref() {
+            self.dissolve = Some(dissolve.clone());
+        }
+        if let Some(flash) = overrides.flash {
+            self.flash = Some(flash);
+        }
+        if let Some(secondary_emitters) = overrides.secondary_emitters.as_ref() {
+            self.secondary_emitters = Some(secondary_emitters.clone());
+        }
+        if let Some(trail) = overrides.trail {
+            self.trail = Some(trail);
+        }
+        if let Some(lod) = overrides.lod {
+            self.lod = Some(lod);
+        }
+        if let Some(factor) = overrides.linvel_scale {
+            self = self.scale_linvel(factor);
+        }
+        if let Some(factor) = overrides.angvel_scale {
+            self = self.scale_angvel(factor);
+        }
+        if let Some(factor) = overrides.lifetime_scale {
+            self = self.scale_lifetime(factor);
+        }
+        self
+    }
+
+    /// Multiplies every possible linear velocity by the given factor.
+    pub fn scale_linvel(mut self, factor: f32) -> Self {
+        self.linvel = property::scale(&self.linvel, factor);
+        self
+    }
+
+    /// Multiplies every possible angular velocity by the given factor.
+    pub fn scale_angvel(mut self, factor: f32) -> Self {
+        self.angvel = property::scale(&self.angvel, factor);
+        self
+    }
+
+    /// Multiplies every possible lifetime by the given factor.
+    pub fn scale_lifetime(mut self, factor: f32) -> Self {
+        self.lifetime = property::scale(&self.lifetime, factor);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy_variable_property::prop_range::PropRange;
+
+    use super::*;
+
+    #[test]
+    fn merge_prefers_the_given_overrides() {
+        let merged = DespawnParticlesPresetOverrides {
+            linvel: Some(Property::Static(1.0)),
+            lifetime: Some(Property::Static(2.0)),
+            tag: Some("base".into()),
+            ..Default::default()
+        }
+        .merge(&DespawnParticlesPresetOverrides {
+            linvel: Some(Property::Static(3.0)),
+            gray: Some(true),
+            ..Default::default()
+        });
+        assert!(matches!(merged.linvel, Some(Property::Static(v)) if v == 3.0));
+        assert!(matches!(merged.lifetime, Some(Property::Static(v)) if v == 2.0));
+        assert_eq!(merged.tag.as_deref(), Some("base"));
+        assert_eq!(merged.gray, Some(true));
+        assert_eq!(merged.shrink, None);
+    }
+
+    #[test]
+    fn merge_multiplies_scales() {
+        let merged = DespawnParticlesPresetOverrides {
+            linvel_scale: Some(2.0),
+            angvel_scale: Some(2.0),
+            ..Default::default()
+        }
+        .merge(&DespawnParticlesPresetOverrides {
+            linvel_scale: Some(3.0),
+            lifetime_scale: Some(4.0),
+            ..Default::default()
+        });
+        assert_eq!(merged.linvel_scale, Some(6.0));
+        assert_eq!(merged.angvel_scale, Some(2.0));
+        assert_eq!(merged.lifetime_scale, Some(4.0));
+    }
+
+    #[test]
+    fn scales_apply_after_the_other_fields() {
+        let preset = DespawnParticlesPreset {
+            linvel: Property::Static(100.0),
+            angvel: Property::Static(1.0),
+            ..DespawnParticlesPreset::new()
+        }
+        .with_overrides(&DespawnParticlesPresetOverrides {
+            linvel: Some(Property::Static(10.0)),
+            linvel_scale: Some(2.0),
+            lifetime: Some((1.0..2.0).into()),
+            lifetime_scale: Some(3.0),
+            angvel_scale: Some(0.5),
+            ..Default::default()
+        });
+        assert!(matches!(preset.linvel, Property::Static(v) if v == 20.0));
+        assert!(matches!(preset.angvel, Property::Static(v) if v == 0.5));
+        assert!(matches!(
+            preset.lifetime,
+            Property::RandomRange(PropRange { start, end, .. }) if start == 3.0 && end == 6.0
+        ));
+    }
+}
@@ -6,8 +6,9 @@ use bevy_ecs::{
 use bevy_reflect::Reflect;
 use bevy_time::{Time, Timer, TimerMode};
 
+use bevy_hierarchy::Parent;
 use bevy_math::{Quat, Vec2};
-use bevy_transform::components::Transform;
+use bevy_transform::components::{GlobalTransform, Transform};
 
 #[derive(Component, Default, Reflect, Copy, Clone)]
 #[reflect(Component)]
@@ -72,7 +73,9 @@ pub(crate) fn phys_tick(
         &mut Velocity,
         &Damping,
         &AdditionalMassProperties,
+        Option<&Parent>,
     )>,
+    global_transforms: Query<&GlobalTransform>,
     mut phys_timer: Local<PhysTimer>,
     time: Res<Time>,
     gravity: Res<Gravity>,
@@ -81,13 +84,23 @@ pub(crate) fn phys_tick(
     if phys_timer.timer.just_finished() {
         let elapsed = time.elapsed_secs() - phys_timer.last_run;
         phys_timer.last_run = time.elapsed_secs();
-        query.par_iter_mut().for_each(|(mut t, mut v, d, m)| {
+        query.par_iter_mut().for_each(|(mut t, mut v, d, m, p)| {
             v.linvel *= 1.0 / (1.0 + (elapsed * d.linear_damping));
             v.angvel *= 1.0 / (1.0 + (elapsed * d.angular_damping));
 
+            // Particles simulated in the local space of another entity need gravity in that
+            // space, see [SimulationSpace][crate::events::SimulationSpace].
+            let gravity = p
+                .and_then(|p| global_transforms.get(p.get()).ok())
+                .map(|parent_transform| {
+                    let (scale, rotation, _) = parent_transform.to_scale_rotation_translation();
+                    (rotation.inverse() * gravity.0.extend(0.0)).truncate() / scale.truncate()
+                })
+                .unwrap_or(gravity.0);
+
             // [m.0.clamp(0.0, 1.0).ceil()] returns 1.0 if mass is non-zero, otherwise 0.0.
             // If an object has 0 mass, then gravity should not apply to it.
-            v.linvel += gravity.0 * elapsed * m.0.clamp(0.0, 1.0).ceil();
+            v.linvel += gravity * elapsed * m.0.clamp(0.0, 1.0).ceil();
 
             t.translation += (v.linvel * elapsed).extend(0.0);
             t.rotation = t.rotation * Quat::from_rotation_z(v.angvel * elapsed);
@@ -0,0 +1,139 @@
+//! Recycles particle entities along with their mesh and material, see
+//! [DespawnParticlesConfig::pooling].
+use bevy_asset::{Assets, Handle};
+use bevy_ecs::{
+    component::Component,
+    entity::Entity,
+    system::{Commands, Query, Res, ResMut, Resource},
+    world::World,
+};
+use bevy_hierarchy::BuildChildren;
+use bevy_math::primitives::Triangle2d;
+use bevy_render::{
+    mesh::{Mesh, Mesh2d},
+    view::{InheritedVisibility, ViewVisibility, Visibility},
+};
+use bevy_sprite::ColorMaterial;
+use bevy_transform::components::{GlobalTransform, Transform};
+
+use crate::{
+    components::DespawnParticle,
+    despawn::DespawnMaterial,
+    resources::{DespawnParticleQueue, DespawnParticlesConfig, ParticleBudgetQueues},
+};
+
+/// A particle entity owned by the [ParticlePool], along with the assets it reuses.
+#[derive(Component, Clone)]
+pub(crate) struct PooledParticle {
+    pub mesh: Handle<Mesh>,
+    pub despawn_material: Handle<DespawnMaterial>,
+    pub color_material: Handle<ColorMaterial>,
+}
+
+/// The pooled particles that are not currently in use.
+#[derive(Resource, Default)]
+pub(crate) struct ParticlePool(pub Vec<Entity>);
+
+impl ParticlePool {
+    /// Takes a particle out of the pool, if there are any left.
+    pub fn acquire(
+        &mut self,
+        pooled_particles: &Query<&PooledParticle>,
+    ) -> Option<(Entity, PooledParticle)> {
+        // Pooled particles can still be despawned by something else, such as the entity they
+        // were simulated relative to, so skip over those.
+        while let Some(entity) = self.0.pop() {
+            if let Ok(pooled_particle) = pooled_particles.get(entity) {
+                return Some((entity, pooled_particle.clone()));
+            }
+        }
+        None
+    }
+}
+
+/// Allocates every pooled particle up front, enough for
+/// [max_particles][DespawnParticlesConfig::max_particles] and each of the
+/// [budgets][DespawnParticlesConfig::budgets].
+pub(crate) fn setup_pool(
+    config: Res<DespawnParticlesConfig>,
+    mut pool: ResMut<ParticlePool>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut despawn_materials: ResMut<Assets<DespawnMaterial>>,
+    mut color_materials: ResMut<Assets<ColorMaterial>>,
+    mut commands: Commands,
+) {
+    if !config.pooling {
+        return;
+    }
+    let capacity = config.max_particles
+        + config
+            .budgets
+            .values()
+            .map(|budget| budget.max_particles)
+            .sum::<usize>();
+    pool.0.reserve(capacity);
+    for _ in 0..capacity {
+        let mesh = meshes.add(Triangle2d::default());
+        let entity = commands
+            .spawn((
+                PooledParticle {
+                    mesh: mesh.clone(),
+                    despawn_material: despawn_materials.add(DespawnMaterial::default()),
+                    color_material: color_materials.add(ColorMaterial::default()),
+                },
+                Mesh2d(mesh),
+                Visibility::Hidden,
+            ))
+            .id();
+        pool.0.push(entity);
+    }
+}
+
+/// Despawns a particle, or returns it to the [ParticlePool] if it came from there.
+pub(crate) fn release_particle(commands: &mut Commands, entity: Entity) {
+    commands.queue(move |world: &mut World| {
+        let Ok(mut entity_mut) = world.get_entity_mut(entity) else {
+            return;
+        };
+        if !entity_mut.contains::<PooledParticle>() {
+            entity_mut.despawn();
+            return;
+        }
+        if !entity_mut.contains::<DespawnParticle>() {
+            // Already released.
+            return;
+        }
+        entity_mut
+            .remove_parent()
+            .retain::<(
+                PooledParticle,
+                Mesh2d,
+                Transform,
+                GlobalTransform,
+                Visibility,
+                InheritedVisibility,
+                ViewVisibility,
+            )>()
+            .insert(Visibility::Hidden);
+        // The entity keeps its id when it is reused, so it must not be counted again.
+        remove_from_queues(world, entity);
+        world.resource_mut::<ParticlePool>().0.push(entity);
+    });
+}
+
+/// Removes the particle from the queue of whichever budget it counts towards, if it is still in
+/// one. Expiring particles are usually the oldest, so they are found near the front.
+fn remove_from_queues(world: &mut World, entity: Entity) {
+    let mut particle_queue = world.resource_mut::<DespawnParticleQueue>();
+    if let Some(index) = particle_queue.0.iter().position(|e| *e == entity) {
+        particle_queue.0.remove(index);
+        return;
+    }
+    let mut budget_queues = world.resource_mut::<ParticleBudgetQueues>();
+    for queue in budget_queues.0.values_mut() {
+        if let Some(index) = queue.iter().position(|e| *e == entity) {
+            queue.remove(index);
+            return;
+        }
+    }
+}
@@ -0,0 +1,166 @@
+//! Serde support for [Property], used through `#[serde(with = "crate::property")]`, along with
+//! other [Property] utilities.
+use bevy_log::warn;
+use bevy_variable_property::{prop_range::PropRange, Property};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+#[derive(Serialize)]
+#[serde(rename = "Property")]
+enum PropertyRef<'a, T> {
+    Static(&'a T),
+    RandomRange {
+        start: &'a T,
+        end: &'a T,
+        #[serde(skip_serializing_if = "is_false")]
+        inclusive: bool,
+    },
+    RandomChoice(&'a [T]),
+    Random,
+}
+
+fn is_false(v: &bool) -> bool {
+    !v
+}
+
+impl<'a, T> From<&'a Property<T>> for PropertyRef<'a, T> {
+    fn from(property: &'a Property<T>) -> Self {
+        match property {
+            Property::Static(v) => PropertyRef::Static(v),
+            Property::RandomRange(range) => PropertyRef::RandomRange {
+                start: &range.start,
+                end: &range.end,
+                inclusive: range.inclusive,
+            },
+            Property::RandomChoice(choices) => PropertyRef::RandomChoice(choices),
+            Property::Random => PropertyRef::Random,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename = "Property")]
+enum PropertyDef<T> {
+    Static(T),
+    RandomRange {
+        start: T,
+        end: T,
+        #[serde(default)]
+        inclusive: bool,
+    },
+    RandomChoice(Vec<T>),
+    Random,
+}
+
+impl<T> From<PropertyDef<T>> for Property<T> {
+    fn from(property: PropertyDef<T>) -> Self {
+        match property {
+            PropertyDef::Static(v) => Property::Static(v),
+            PropertyDef::RandomRange {
+                start,
+                end,
+                inclusive,
+            } => Property::RandomRange(PropRange::new(start, end, inclusive)),
+            PropertyDef::RandomChoice(choices) => Property::RandomChoice(choices),
+            PropertyDef::Random => Property::Random,
+        }
+    }
+}
+
+pub fn serialize<T: Serialize, S: Serializer>(
+    property: &Property<T>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    PropertyRef::from(property).serialize(serializer)
+}
+
+pub fn deserialize<'de, T: Deserialize<'de>, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Property<T>, D::Error> {
+    Ok(PropertyDef::deserialize(deserializer)?.into())
+}
+
+/// Serde support for an optional [Property], used through
+/// `#[serde(with = "crate::property::option")]`.
+pub mod option {
+    use super::*;
+
+    pub fn serialize<T: Serialize, S: Serializer>(
+        property: &Option<Property<T>>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        property
+            .as_ref()
+            .map(PropertyRef::from)
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, T: Deserialize<'de>, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Property<T>>, D::Error> {
+        Ok(Option::<PropertyDef<T>>::deserialize(deserializer)?.map(Into::into))
+    }
+}
+
+/// Multiplies every value the given property can produce by the given factor.
+pub(crate) fn scale(property: &Property<f32>, factor: f32) -> Property<f32> {
+    match property {
+        Property::Static(v) => Property::Static(v * factor),
+        Property::RandomRange(range) => Property::RandomRange(PropRange::new(
+            range.start * factor,
+            range.end * factor,
+            range.inclusive,
+        )),
+        Property::RandomChoice(choices) => {
+            Property::RandomChoice(choices.iter().map(|v| v * factor).collect())
+        }
+        Property::Random => {
+            warn!("Cannot scale a Random Property, leaving it as is");
+            property.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize, Deserialize)]
+    struct Wrapper(#[serde(with = "crate::property")] Property<f32>);
+
+    fn round_trip(property: Property<f32>) -> Property<f32> {
+        let serialized = ron::to_string(&Wrapper(property)).unwrap();
+        ron::from_str::<Wrapper>(&serialized).unwrap().0
+    }
+
+    #[test]
+    fn round_trips_every_variant() {
+        assert!(matches!(round_trip(Property::Static(1.5)), Property::Static(v) if v == 1.5));
+        assert!(matches!(
+            round_trip((1.0..2.0).into()),
+            Property::RandomRange(PropRange { start, end, inclusive: false })
+                if start == 1.0 && end == 2.0
+        ));
+        assert!(matches!(
+            round_trip((1.0..=2.0).into()),
+            Property::RandomRange(PropRange { start, end, inclusive: true })
+                if start == 1.0 && end == 2.0
+        ));
+        assert!(matches!(
+            round_trip(Property::RandomChoice(vec![1.0, 2.0])),
+            Property::RandomChoice(choices) if choices == [1.0, 2.0]
+        ));
+        assert!(matches!(round_trip(Property::Random), Property::Random));
+    }
+
+    #[test]
+    fn inclusive_defaults_to_false() {
+        let Wrapper(property) = ron::from_str("(RandomRange(start: 1.0, end: 2.0))").unwrap();
+        assert!(matches!(
+            property,
+            Property::RandomRange(PropRange {
+                inclusive: false,
+                ..
+            })
+        ));
+    }
+}
@@ -0,0 +1,160 @@
+//! A registry of named [DespawnParticlesPreset]s
+use std::{borrow::Cow, collections::HashMap};
+
+use bevy_asset::{AssetEvent, AssetId, Assets, Handle};
+use bevy_ecs::{
+    change_detection::DetectChangesMut,
+    entity::Entity,
+    event::EventReader,
+    system::{Res, ResMut, Resource},
+};
+
+use crate::events::{DespawnParticlesEvent, DespawnParticlesPreset};
+
+/// The key a preset is registered under in the [DespawnPresetRegistry]. Can be created from
+/// strings, implement `From<T> for DespawnPresetKey` to use your own key types.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct DespawnPresetKey(pub Cow<'static, str>);
+
+impl From<&'static str> for DespawnPresetKey {
+    fn from(key: &'static str) -> Self {
+        Self(Cow::Borrowed(key))
+    }
+}
+
+impl From<String> for DespawnPresetKey {
+    fn from(key: String) -> Self {
+        Self(Cow::Owned(key))
+    }
+}
+
+enum RegisteredPreset {
+    Preset(DespawnParticlesPreset),
+    Asset {
+        handle: Handle<DespawnParticlesPreset>,
+        // Copy of the asset, kept up to date by [sync_preset_registry].
+        loaded: Option<DespawnParticlesPreset>,
+        stale: bool,
+    },
+}
+
+impl RegisteredPreset {
+    fn from_handle(handle: Handle<DespawnParticlesPreset>) -> Self {
+        Self::Asset {
+            handle,
+            loaded: None,
+            stale: true,
+        }
+    }
+
+    fn get(&self) -> Option<&DespawnParticlesPreset> {
+        match self {
+            Self::Preset(preset) => Some(preset),
+            Self::Asset { loaded, .. } => loaded.as_ref(),
+        }
+    }
+}
+
+/// Maps keys to [DespawnParticlesPreset]s, either given directly or as handles to preset
+/// assets, so that effects can be referred to by name.
+///
+/// A default preset can be set which is used whenever a key is not registered.
+#[derive(Resource, Default)]
+pub struct DespawnPresetRegistry {
+    presets: HashMap<DespawnPresetKey, RegisteredPreset>,
+    default: Option<RegisteredPreset>,
+}
+
+impl DespawnPresetRegistry {
+    /// Registers the preset under the given key, replacing any preset registered under it.
+    pub fn insert<K: Into<DespawnPresetKey>>(&mut self, key: K, preset: DespawnParticlesPreset) {
+        self.presets
+            .insert(key.into(), RegisteredPreset::Preset(preset));
+    }
+
+    /// Registers the preset asset under the given key, replacing any preset registered under it.
+    /// The preset is available once the asset has loaded, and follows any changes to the asset.
+    pub fn insert_handle<K: Into<DespawnPresetKey>>(
+        &mut self,
+        key: K,
+        handle: Handle<DespawnParticlesPreset>,
+    ) {
+        self.presets
+            .insert(key.into(), RegisteredPreset::from_handle(handle));
+    }
+
+    /// Unregisters the preset under the given key.
+    pub fn remove<K: Into<DespawnPresetKey>>(&mut self, key: K) {
+        self.presets.remove(&key.into());
+    }
+
+    /// Sets the preset used for keys that are not registered.
+    pub fn set_default(&mut self, preset: DespawnParticlesPreset) {
+        self.default = Some(RegisteredPreset::Preset(preset));
+    }
+
+    /// Sets the preset asset used for keys that are not registered.
+    pub fn set_default_handle(&mut self, handle: Handle<DespawnParticlesPreset>) {
+        self.default = Some(RegisteredPreset::from_handle(handle));
+    }
+
+    /// Returns true if a preset is registered under the given key, ignoring the default.
+    pub fn contains<K: Into<DespawnPresetKey>>(&self, key: K) -> bool {
+        self.presets.contains_key(&key.into())
+    }
+
+    /// Gets the preset registered under the given key, or the default preset if there is none.
+    /// Returns None if neither are available, or if the preset asset has not loaded yet.
+    pub fn get<K: Into<DespawnPresetKey>>(&self, key: K) -> Option<&DespawnParticlesPreset> {
+        self.presets
+            .get(&key.into())
+            .or(self.default.as_ref())
+            .and_then(RegisteredPreset::get)
+    }
+
+    /// Creates an event for the given entity from the preset registered under the given key.
+    /// See [DespawnPresetRegistry::get].
+    pub fn create_event<K: Into<DespawnPresetKey>>(
+        &self,
+        key: K,
+        entity: Entity,
+    ) -> Option<DespawnParticlesEvent> {
+        self.get(key).map(|preset| preset.create_event(entity))
+    }
+
+    fn entries_mut(&mut self) -> impl Iterator<Item = &mut RegisteredPreset> {
+        self.presets.values_mut().chain(self.default.as_mut())
+    }
+}
+
+/// Keeps the copies of preset assets held by the [DespawnPresetRegistry] up to date.
+pub(crate) fn sync_preset_registry(
+    mut asset_events: EventReader<AssetEvent<DespawnParticlesPreset>>,
+    presets: Res<Assets<DespawnParticlesPreset>>,
+    mut registry: ResMut<DespawnPresetRegistry>,
+) {
+    let changed = asset_events
+        .read()
+        .filter_map(|event| match event {
+            AssetEvent::Added { id } | AssetEvent::Modified { id } | AssetEvent::Removed { id } => {
+                Some(*id)
+            }
+            _ => None,
+        })
+        .collect::<Vec<AssetId<DespawnParticlesPreset>>>();
+
+    // Avoid flagging the registry as changed every frame.
+    for entry in registry.bypass_change_detection().entries_mut() {
+        if let RegisteredPreset::Asset {
+            handle,
+            loaded,
+            stale,
+        } = entry
+        {
+            if *stale || changed.contains(&handle.id()) {
+                *loaded = presets.get(handle).cloned();
+                *stale = loaded.is_none();
+            }
+        }
+    }
+}
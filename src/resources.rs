@@ -1,19 +1,150 @@
-use std::collections::VecDeque;
+use std::{
+    borrow::Cow,
+    collections::{HashMap, VecDeque},
+};
 
 use bevy_ecs::prelude::{Entity, Resource};
 
+use crate::{adaptive::AdaptiveQuality, events::DespawnEffectId, lod::LodPolicy};
+
+/// How long particles evicted by [EvictionPolicy::FadeOut] take to fade and shrink away when no
+/// eviction duration is set, in seconds.
+pub const DEFAULT_EVICTION_DURATION: f32 = 0.15;
+
 #[derive(Resource)]
 pub struct DespawnParticlesConfig {
     pub max_particles: usize,
+
+    /// Which particles are evicted once there are more than
+    /// [max_particles][DespawnParticlesConfig::max_particles].
+    pub eviction: EvictionPolicy,
+
+    /// How long evicted particles take to fade and shrink away, in seconds. They stop counting
+    /// towards [max_particles][DespawnParticlesConfig::max_particles] right away. When zero,
+    /// which is the default, evicted particles are despawned immediately unless the eviction is
+    /// [EvictionPolicy::FadeOut].
+    pub eviction_duration: f32,
+
+    /// Named budgets that events can opt into with
+    /// [DespawnParticlesEvent::budget][crate::events::DespawnParticlesEvent::budget]. Each
+    /// budget is capped separately, so particles in one budget never evict particles in another.
+    pub budgets: HashMap<Cow<'static, str>, ParticleBudget>,
+
+    /// The maximum number of [SecondaryParticles][crate::secondary::SecondaryParticle], which
+    /// are counted separately from the fragments.
+    pub max_secondary_particles: usize,
+
+    /// Whether particle entities, along with their mesh and material, are allocated once at
+    /// startup and reused instead of being spawned and despawned for every effect. Enough are
+    /// allocated for [max_particles][DespawnParticlesConfig::max_particles] and each budget, and
+    /// particles beyond that are spawned as usual.
+    pub pooling: bool,
+
+    /// Scales down the number of particles of every event based on how the entity appears to
+    /// the cameras, unless the event sets its own
+    /// [lod][crate::events::DespawnParticlesEvent::lod].
+    pub lod: Option<LodPolicy>,
+
+    /// When set, the number of particles is lowered and events are deferred while the game is
+    /// struggling, see [AdaptiveQuality].
+    pub adaptive_quality: Option<AdaptiveQuality>,
 }
 
 impl Default for DespawnParticlesConfig {
     fn default() -> Self {
         Self {
             max_particles: 1024,
+            eviction: EvictionPolicy::Oldest,
+            eviction_duration: 0.0,
+            budgets: HashMap::new(),
+            max_secondary_particles: 512,
+            pooling: false,
+            lod: None,
+            adaptive_quality: None,
         }
     }
 }
 
+impl DespawnParticlesConfig {
+    /// Adds a named budget, see [DespawnParticlesConfig::budgets].
+    pub fn with_budget<T: Into<Cow<'static, str>>>(
+        mut self,
+        name: T,
+        budget: ParticleBudget,
+    ) -> Self {
+        self.budgets.insert(name.into(), budget);
+        self
+    }
+}
+
+/// A cap on the number of particles of the events that opt into it, see
+/// [DespawnParticlesConfig::budgets].
+#[derive(Clone, Debug)]
+pub struct ParticleBudget {
+    /// The maximum number of particles in this budget.
+    pub max_particles: usize,
+
+    /// Which particles are evicted once there are more than
+    /// [max_particles][ParticleBudget::max_particles].
+    pub eviction: EvictionPolicy,
+
+    /// How long evicted particles take to fade and shrink away, in seconds, see
+    /// [DespawnParticlesConfig::eviction_duration].
+    pub eviction_duration: f32,
+}
+
+impl ParticleBudget {
+    pub fn new(max_particles: usize) -> Self {
+        Self {
+            max_particles,
+            eviction: EvictionPolicy::Oldest,
+            eviction_duration: 0.0,
+        }
+    }
+
+    /// See [ParticleBudget::eviction]
+    pub fn with_eviction(mut self, eviction: EvictionPolicy) -> Self {
+        self.eviction = eviction;
+        self
+    }
+
+    /// See [ParticleBudget::eviction_duration]
+    pub fn with_eviction_duration(mut self, eviction_duration: f32) -> Self {
+        self.eviction_duration = eviction_duration;
+        self
+    }
+}
+
+/// Which particles are evicted once a budget is exceeded.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EvictionPolicy {
+    /// The oldest particles are despawned. This is the default.
+    #[default]
+    Oldest,
+
+    /// The particles furthest from the closest camera are despawned. Falls back to the oldest
+    /// particles when there is no camera.
+    FurthestFromCamera,
+
+    /// The particles with the smallest area are despawned.
+    Smallest,
+
+    /// The oldest particles fade and shrink away instead of being despawned immediately. When
+    /// the eviction duration is zero, [DEFAULT_EVICTION_DURATION] is used.
+    FadeOut,
+}
+
 #[derive(Resource, Default)]
 pub struct DespawnParticleQueue(pub VecDeque<Entity>);
+
+/// The particles of each of the [DespawnParticlesConfig::budgets], oldest first.
+#[derive(Resource, Default)]
+pub struct ParticleBudgetQueues(pub HashMap<Cow<'static, str>, VecDeque<Entity>>);
+
+#[derive(Resource, Default)]
+pub struct SecondaryParticleQueue(pub VecDeque<Entity>);
+
+/// Maps the effects that still have particles alive to the entity holding their
+/// [DespawnParticlesEffectRoot][crate::components::DespawnParticlesEffectRoot].
+#[derive(Resource, Default)]
+pub(crate) struct ActiveDespawnEffects(pub HashMap<DespawnEffectId, Entity>);
@@ -0,0 +1,453 @@
+//! Lightweight particles spawned alongside the fragments of a [DespawnParticlesEvent], such as
+//! sparks, dust and smoke.
+//!
+//! [DespawnParticlesEvent]: crate::events::DespawnParticlesEvent
+use std::sync::Arc;
+
+use bevy_ecs::{
+    component::Component,
+    entity::Entity,
+    query::With,
+    system::{Commands, Query, Res, ResMut},
+};
+use bevy_math::{Vec2, Vec3};
+use bevy_render::view::{RenderLayers, Visibility};
+use bevy_sprite::Sprite;
+use bevy_time::{Time, Timer, TimerMode};
+use bevy_transform::components::{GlobalTransform, Transform};
+
+use bevy_variable_property::prelude::*;
+use bevy_variable_property::Property;
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "bevy_rapier2d")]
+use bevy_rapier2d::prelude::Velocity;
+
+#[cfg(not(feature = "bevy_rapier2d"))]
+use crate::phys::Velocity;
+
+use crate::{
+    gradient::ColorGradient,
+    resources::{DespawnParticlesConfig, SecondaryParticleQueue},
+};
+
+/// The secondary particles to spawn for a
+/// [DespawnParticlesEvent][crate::events::DespawnParticlesEvent]. These are counted separately
+/// from the fragments, see [DespawnParticlesConfig::max_secondary_particles].
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SecondaryEmitters {
+    /// Spawned from the center of the entity when it shatters.
+    pub sparks: Option<Sparks>,
+
+    /// Spawned where fragments land.
+    pub dust: Option<Dust>,
+
+    /// Spawned behind fast fragments.
+    pub smoke: Option<Smoke>,
+}
+
+impl SecondaryEmitters {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// See [SecondaryEmitters::sparks]
+    pub fn with_sparks(mut self, sparks: Sparks) -> Self {
+        self.sparks = Some(sparks);
+        self
+    }
+
+    /// See [SecondaryEmitters::dust]
+    pub fn with_dust(mut self, dust: Dust) -> Self {
+        self.dust = Some(dust);
+        self
+    }
+
+    /// See [SecondaryEmitters::smoke]
+    pub fn with_smoke(mut self, smoke: Smoke) -> Self {
+        self.smoke = Some(smoke);
+        self
+    }
+}
+
+/// How a single secondary particle looks and moves.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SecondaryParticleStyle {
+    /// In seconds
+    #[serde(with = "crate::property")]
+    pub lifetime: Property<f32>,
+
+    /// The speed the particle moves at in a random direction.
+    #[serde(with = "crate::property")]
+    pub speed: Property<f32>,
+
+    /// The width and height of the particle.
+    #[serde(with = "crate::property")]
+    pub size: Property<f32>,
+
+    /// Decelerates the particle, like the damping of the fragments.
+    pub damping: f32,
+
+    /// The color of the particle over its lifetime.
+    pub color_over_lifetime: ColorGradient,
+}
+
+impl Default for SecondaryParticleStyle {
+    fn default() -> Self {
+        Self {
+            lifetime: 0.5.into(),
+            speed: 0.0.into(),
+            size: 2.0.into(),
+            damping: 0.0,
+            color_over_lifetime: ColorGradient::default(),
+        }
+    }
+}
+
+impl SecondaryParticleStyle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// See [SecondaryParticleStyle::lifetime]
+    pub fn with_lifetime<T: Into<Property<f32>>>(mut self, v: T) -> Self {
+        self.lifetime = v.into();
+        self
+    }
+
+    /// See [SecondaryParticleStyle::speed]
+    pub fn with_speed<T: Into<Property<f32>>>(mut self, v: T) -> Self {
+        self.speed = v.into();
+        self
+    }
+
+    /// See [SecondaryParticleStyle::size]
+    pub fn with_size<T: Into<Property<f32>>>(mut self, v: T) -> Self {
+        self.size = v.into();
+        self
+    }
+
+    /// See [SecondaryParticleStyle::damping]
+    pub fn with_damping(mut self, damping: f32) -> Self {
+        self.damping = damping;
+        self
+    }
+
+    /// See [SecondaryParticleStyle::color_over_lifetime]
+    pub fn with_color_over_lifetime<T: Into<ColorGradient>>(mut self, gradient: T) -> Self {
+        self.color_over_lifetime = gradient.into();
+        self
+    }
+}
+
+/// Particles spawned from the center of the entity when it shatters.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Sparks {
+    #[serde(with = "crate::property")]
+    pub count: Property<usize>,
+    pub style: SecondaryParticleStyle,
+}
+
+impl Default for Sparks {
+    fn default() -> Self {
+        Self {
+            count: 16.into(),
+            style: SecondaryParticleStyle::default(),
+        }
+    }
+}
+
+impl Sparks {
+    pub fn new<T: Into<Property<usize>>>(count: T, style: SecondaryParticleStyle) -> Self {
+        Self {
+            count: count.into(),
+            style,
+        }
+    }
+}
+
+/// Particles spawned once a fragment lands, which is when it slows down below
+/// [Dust::landing_speed] after moving faster than it.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Dust {
+    /// The number of particles spawned per fragment.
+    #[serde(with = "crate::property")]
+    pub count: Property<usize>,
+    pub landing_speed: f32,
+    pub style: SecondaryParticleStyle,
+}
+
+impl Default for Dust {
+    fn default() -> Self {
+        Self {
+            count: 3.into(),
+            landing_speed: 20.0,
+            style: SecondaryParticleStyle::default(),
+        }
+    }
+}
+
+impl Dust {
+    pub fn new<T: Into<Property<usize>>>(count: T, style: SecondaryParticleStyle) -> Self {
+        Self {
+            count: count.into(),
+            style,
+            ..Default::default()
+        }
+    }
+
+    /// See [Dust::landing_speed]
+    pub fn with_landing_speed(mut self, landing_speed: f32) -> Self {
+        self.landing_speed = landing_speed;
+        self
+    }
+}
+
+/// Particles spawned behind fragments while they move faster than [Smoke::min_speed].
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Smoke {
+    /// The number of particles spawned per second, per fragment.
+    pub rate: f32,
+    pub min_speed: f32,
+    pub style: SecondaryParticleStyle,
+}
+
+impl Default for Smoke {
+    fn default() -> Self {
+        Self {
+            rate: 20.0,
+            min_speed: 100.0,
+            style: SecondaryParticleStyle::default(),
+        }
+    }
+}
+
+impl Smoke {
+    pub fn new(rate: f32, style: SecondaryParticleStyle) -> Self {
+        Self {
+            rate,
+            style,
+            ..Default::default()
+        }
+    }
+
+    /// See [Smoke::min_speed]
+    pub fn with_min_speed(mut self, min_speed: f32) -> Self {
+        self.min_speed = min_speed;
+        self
+    }
+}
+
+/// A secondary particle, rendered as a plain colored [Sprite].
+#[derive(Component)]
+pub struct SecondaryParticle {
+    /// When this timer ends, the particle will despawn.
+    pub lifetime: Timer,
+    pub velocity: Vec2,
+    style: Arc<SecondaryParticleStyle>,
+}
+
+/// Spawns [Dust] once the fragment lands.
+#[derive(Component)]
+pub(crate) struct DustOnLanding {
+    dust: Arc<Dust>,
+    style: Arc<SecondaryParticleStyle>,
+    moving: bool,
+}
+
+impl DustOnLanding {
+    pub fn new((dust, style): &(Arc<Dust>, Arc<SecondaryParticleStyle>)) -> Self {
+        Self {
+            dust: dust.clone(),
+            style: style.clone(),
+            moving: false,
+        }
+    }
+}
+
+/// Spawns [Smoke] behind the fragment.
+#[derive(Component)]
+pub(crate) struct SmokeTrail {
+    smoke: Arc<Smoke>,
+    style: Arc<SecondaryParticleStyle>,
+    timer: Timer,
+}
+
+impl SmokeTrail {
+    pub fn new((smoke, style): &(Arc<Smoke>, Arc<SecondaryParticleStyle>)) -> Self {
+        let timer = Timer::from_seconds(1.0 / smoke.rate.max(f32::EPSILON), TimerMode::Repeating);
+        Self {
+            smoke: smoke.clone(),
+            style: style.clone(),
+            timer,
+        }
+    }
+}
+
+/// The shared parts of the [SecondaryEmitters] of fragments, created once per event rather than
+/// per fragment.
+pub(crate) struct SharedSecondaryEmitters {
+    pub dust: Option<(Arc<Dust>, Arc<SecondaryParticleStyle>)>,
+    pub smoke: Option<(Arc<Smoke>, Arc<SecondaryParticleStyle>)>,
+}
+
+impl From<&SecondaryEmitters> for SharedSecondaryEmitters {
+    fn from(emitters: &SecondaryEmitters) -> Self {
+        Self {
+            dust: emitters
+                .dust
+                .as_ref()
+                .map(|dust| (Arc::new(dust.clone()), Arc::new(dust.style.clone()))),
+            smoke: emitters
+                .smoke
+                .as_ref()
+                .map(|smoke| (Arc::new(smoke.clone()), Arc::new(smoke.style.clone()))),
+        }
+    }
+}
+
+/// Spawns `count` secondary particles at the given position, moving in random directions.
+pub(crate) fn spawn_secondary_particles(
+    commands: &mut Commands,
+    queue: &mut SecondaryParticleQueue,
+    style: &Arc<SecondaryParticleStyle>,
+    position: Vec3,
+    count: usize,
+    visibility: Visibility,
+    render_layers: Option<&RenderLayers>,
+) {
+    for _ in 0..count {
+        let angle = rand::random::<f32>() * std::f32::consts::TAU;
+        let velocity = Vec2::from_angle(angle) * style.speed.get_value();
+        let mut entity_commands = commands.spawn((
+            SecondaryParticle {
+                lifetime: Timer::from_seconds(style.lifetime.get_value(), TimerMode::Once),
+                velocity,
+                style: style.clone(),
+            },
+            Sprite::from_color(style.color_over_lifetime.sample(0.0), Vec2::ONE),
+            Transform::from_translation(position)
+                .with_scale(Vec2::splat(style.size.get_value()).extend(1.0)),
+            visibility,
+        ));
+        if let Some(render_layers) = render_layers {
+            entity_commands.insert(render_layers.clone());
+        }
+        queue.0.push_back(entity_commands.id());
+    }
+}
+
+pub(crate) fn handle_secondary_particles(
+    mut secondary_particles: Query<(Entity, &mut SecondaryParticle, &mut Transform, &mut Sprite)>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    let delta = time.delta_secs();
+    for (entity, mut particle, mut transform, mut sprite) in secondary_particles.iter_mut() {
+        particle.lifetime.tick(time.delta());
+        if particle.lifetime.finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        let damping = 1.0 / (1.0 + delta * particle.style.damping);
+        particle.velocity *= damping;
+        transform.translation += (particle.velocity * delta).extend(0.0);
+        sprite.color = particle
+            .style
+            .color_over_lifetime
+            .sample(particle.lifetime.fraction())
+            .into();
+    }
+}
+
+pub(crate) fn emit_dust(
+    mut fragments: Query<(
+        &Velocity,
+        &GlobalTransform,
+        &mut DustOnLanding,
+        &Visibility,
+        Option<&RenderLayers>,
+    )>,
+    mut queue: ResMut<SecondaryParticleQueue>,
+    mut commands: Commands,
+) {
+    for (velocity, global_transform, mut dust_on_landing, visibility, render_layers) in
+        fragments.iter_mut()
+    {
+        let moving = velocity.linvel.length() > dust_on_landing.dust.landing_speed;
+        if dust_on_landing.moving && !moving {
+            spawn_secondary_particles(
+                &mut commands,
+                &mut queue,
+                &dust_on_landing.style,
+                global_transform.translation(),
+                dust_on_landing.dust.count.get_value(),
+                *visibility,
+                render_layers,
+            );
+        }
+        dust_on_landing.moving = moving;
+    }
+}
+
+pub(crate) fn emit_smoke(
+    mut fragments: Query<(
+        &Velocity,
+        &GlobalTransform,
+        &mut SmokeTrail,
+        &Visibility,
+        Option<&RenderLayers>,
+    )>,
+    mut queue: ResMut<SecondaryParticleQueue>,
+    time: Res<Time>,
+    mut commands: Commands,
+) {
+    for (velocity, global_transform, mut smoke_trail, visibility, render_layers) in
+        fragments.iter_mut()
+    {
+        if velocity.linvel.length() < smoke_trail.smoke.min_speed {
+            continue;
+        }
+        smoke_trail.timer.tick(time.delta());
+        let count = smoke_trail.timer.times_finished_this_tick() as usize;
+        if count > 0 {
+            spawn_secondary_particles(
+                &mut commands,
+                &mut queue,
+                &smoke_trail.style,
+                global_transform.translation(),
+                count,
+                *visibility,
+                render_layers,
+            );
+        }
+    }
+}
+
+/// Despawns the oldest secondary particles once there are more than
+/// [DespawnParticlesConfig::max_secondary_particles].
+pub(crate) fn max_secondary_particles_check(
+    mut queue: ResMut<SecondaryParticleQueue>,
+    config: Res<DespawnParticlesConfig>,
+    secondary_particles: Query<(), With<SecondaryParticle>>,
+    mut commands: Commands,
+) {
+    // Secondary particles are short-lived, so forget about the ones that already expired to keep
+    // the count accurate.
+    queue
+        .0
+        .retain(|entity| secondary_particles.contains(*entity));
+    while queue.0.len() > config.max_secondary_particles {
+        if let Some(entity) = queue.0.pop_front() {
+            if let Some(mut entity_commands) = commands.get_entity(entity) {
+                entity_commands.despawn();
+            }
+        }
+    }
+}
@@ -1,17 +1,21 @@
 use bevy_asset::{Assets, Handle};
-use bevy_color::{palettes::basic::GRAY, Alpha, LinearRgba};
+use bevy_color::{palettes::basic::GRAY, Alpha, LinearRgba, Mix};
 use bevy_ecs::{
     entity::Entity,
     event::EventReader,
-    query::AnyOf,
-    system::{Commands, EntityCommands, Query, Res, ResMut},
+    observer::Trigger,
+    query::{AnyOf, With, Without},
+    system::{Commands, In, Query, Res, ResMut, SystemParam},
+    world::{OnRemove, World},
 };
 use bevy_math::{primitives::Rectangle, Vec2};
 
 use bevy_render::{
-    mesh::{Indices, VertexAttributeValues},
+    camera::Camera,
+    mesh::{Indices, MeshAabb, VertexAttributeValues},
     prelude::Visibility,
     render_resource::PrimitiveTopology,
+    view::{InheritedVisibility, RenderLayers},
 };
 use bevy_render::{
     mesh::{Mesh, Mesh2d},
@@ -19,12 +23,13 @@ use bevy_render::{
 };
 use bevy_sprite::MeshMaterial2d;
 
-use bevy_hierarchy::DespawnRecursiveExt;
+use bevy_hierarchy::{BuildChildren, Children, DespawnRecursiveExt, HierarchyQueryExt, Parent};
 use bevy_image::Image;
 use bevy_log::{error, warn};
 use bevy_math::Vec3;
 use bevy_sprite::{ColorMaterial, Sprite, TextureAtlasLayout};
-use bevy_time::Time;
+use bevy_tasks::{ComputeTaskPool, TaskPool};
+use bevy_time::{Time, Timer, TimerMode};
 use bevy_transform::components::{GlobalTransform, Transform};
 
 #[cfg(feature = "bevy_rapier2d")]
@@ -33,17 +38,35 @@ use bevy_rapier2d::prelude::*;
 use bevy_variable_property::prelude::*;
 
 use smallvec::SmallVec;
+use std::{
+    collections::{hash_map::Entry, HashMap, VecDeque},
+    sync::Arc,
+};
 use thiserror::Error;
 
 #[cfg(not(feature = "bevy_rapier2d"))]
 use crate::phys::{Damping, Velocity};
 
 use crate::{
+    adaptive::AdaptiveQualityState,
     components::*,
     despawn::DespawnMaterial,
-    events::DespawnParticlesEvent,
-    resources::{DespawnParticleQueue, DespawnParticlesConfig},
-    utils::{angle_between3, float32x3_sub, float32x3_triangle_centroid},
+    events::{
+        DespawnBlendMode, DespawnParticlesEvent, DespawnParticlesFinished, ShrinkPivot,
+        SimulationSpace, SourceMode,
+    },
+    gradient::multiply,
+    pool::{release_particle, ParticlePool, PooledParticle},
+    resources::{
+        ActiveDespawnEffects, DespawnParticleQueue, DespawnParticlesConfig, EvictionPolicy,
+        ParticleBudget, ParticleBudgetQueues, SecondaryParticleQueue, DEFAULT_EVICTION_DURATION,
+    },
+    secondary::{spawn_secondary_particles, DustOnLanding, SharedSecondaryEmitters, SmokeTrail},
+    trail::{average_color, spawn_ribbon, FragmentTrail, TrailMaterial},
+    utils::{
+        angle_between3, float32x3_centroid, float32x3_sub, float32x3_triangle_centroid,
+        triangle_list_area,
+    },
 };
 
 #[derive(Debug)]
@@ -96,155 +119,144 @@ pub enum DespawnParticlesError {
 
 pub fn setup(
     mut despawn_particles_queue: ResMut<DespawnParticleQueue>,
+    mut budget_queues: ResMut<ParticleBudgetQueues>,
+    mut secondary_particle_queue: ResMut<SecondaryParticleQueue>,
     config: Res<DespawnParticlesConfig>,
 ) {
     // Start with the correct capacity to avoid unnecessary allocations. Additional allocation will
     // likely occur after this though
     despawn_particles_queue.0 = std::collections::VecDeque::with_capacity(config.max_particles);
+    for (name, budget) in config.budgets.iter() {
+        budget_queues.0.insert(
+            name.clone(),
+            std::collections::VecDeque::with_capacity(budget.max_particles),
+        );
+    }
+    secondary_particle_queue.0 =
+        std::collections::VecDeque::with_capacity(config.max_secondary_particles);
 }
 
-fn handle_despawn_particles_event(
+/// The visibility of the particles generated from `target` for an event targeting `entity`.
+/// Particles are not part of the hierarchy of the entity, so they are hidden if the entity was
+/// not visible. Hiding the entity for this effect does not count.
+fn particle_visibility(
+    visibilities: &Query<(
+        &Visibility,
+        Option<&InheritedVisibility>,
+        Option<&HiddenDespawnSource>,
+    )>,
+    entity: Entity,
+    target: Entity,
+) -> Visibility {
+    let hidden_source = visibilities
+        .get(entity)
+        .ok()
+        .and_then(|(_, _, hidden_source)| hidden_source);
+    match (hidden_source, visibilities.get(target)) {
+        (Some(hidden_source), _) if hidden_source.previous_visibility == Visibility::Hidden => {
+            Visibility::Hidden
+        }
+        (Some(_), Ok((Visibility::Hidden, _, _))) if target != entity => Visibility::Hidden,
+        (None, Ok((_, Some(inherited_visibility), _))) if !inherited_visibility.get() => {
+            Visibility::Hidden
+        }
+        _ => Visibility::Inherited,
+    }
+}
+
+/// Despawns or hides the target entity of the event, depending on its [SourceMode]. Returns false
+/// if the entity does not exist.
+fn apply_source_mode(
     event: &DespawnParticlesEvent,
     commands: &mut Commands,
-    images: &Assets<Image>,
-    meshes: &mut Assets<Mesh>,
-    atlas_layouts: &Assets<TextureAtlasLayout>,
-    global_transforms: &Query<&GlobalTransform>,
-    despawn_materials: &mut Assets<DespawnMaterial>,
-    sprites: &Query<&Sprite>,
-    mesh_components: &Query<(&Mesh2d, Option<&MeshMaterial2d<ColorMaterial>>)>,
-    color_materials: &mut Assets<ColorMaterial>,
-    no_death_animations: &Query<&NoDespawnAnimation>,
-    velocities: &Query<&Velocity>,
-    despawn_mesh_overrides: &Query<&DespawnMeshOverride>,
-    despawn_particle_queue: &mut DespawnParticleQueue,
-) -> Result<(), DespawnParticlesError> {
+    visibilities: &Query<(
+        &Visibility,
+        Option<&InheritedVisibility>,
+        Option<&HiddenDespawnSource>,
+    )>,
+    shatter_on_despawns: &Query<(), With<ShatterOnDespawn>>,
+    targets: &[Entity],
+) -> bool {
     let DespawnParticlesEvent {
         entity,
-        linvel,
-        linvel_addtl,
-        angvel,
-        ignore_parent_phys,
-        lifetime,
-        linear_damping,
-        angular_damping,
-        mass,
-        shrink,
-        fade,
-        mesh_override: event_mesh_override,
-        target_num_particles,
-        gray,
+        effect_id,
         recurse,
+        source_mode,
+        ..
     } = event;
-    let target_num_particles = target_num_particles.get_value();
-
-    let gray: u32 = gray.then(|| 1).unwrap_or(0); // Need to convert for shader
-
-    // Use closures so we don't have to re-do the if statement for every single particle.
-    // This assumes the no-op actually gets optimized out, which is may not..
-    let shrink_spawn_func = if *shrink {
-        |entity_cmds: &mut EntityCommands| {
-            entity_cmds.insert(ShrinkingDespawnParticle);
-        }
-    } else {
-        |_entity_cmds: &mut EntityCommands| {}
-    };
-
-    let fade_spawn_func = if *fade {
-        |entity_cmds: &mut EntityCommands| {
-            entity_cmds.insert(FadingDespawnParticle);
+    if commands.get_entity(*entity).is_none() {
+        return false;
+    }
+    if *source_mode == SourceMode::Despawn {
+        // Particles are being generated by this event already. This has to be applied before
+        // the despawn, which is when ShatterOnDespawn generates its own.
+        for target in targets {
+            if shatter_on_despawns.contains(*target) {
+                commands.entity(*target).try_insert(ShatterOnDespawnHandled);
+            }
         }
-    } else {
-        |_entity_cmds: &mut EntityCommands| {}
-    };
-
-    if let Some(mut entity_commands) = commands.get_entity(*entity) {
-        if *recurse {
-            entity_commands.despawn_recursive();
-        } else {
-            entity_commands.despawn();
+    }
+    let mut entity_commands = commands.entity(*entity);
+    match source_mode {
+        SourceMode::Despawn => {
+            if *recurse {
+                entity_commands.despawn_recursive();
+            } else {
+                entity_commands.despawn();
+            }
         }
-        // Now spawn the death animation, if possible
-        if no_death_animations.get(*entity).is_ok() {
-            // We ignore death animations for this object.
-            return Ok(());
+        SourceMode::Keep => {}
+        SourceMode::HideAndRestore | SourceMode::HideAndDespawn => {
+            // If the entity is already hidden by a previous event, keep the visibility it had
+            // before that one.
+            let previous_visibility = visibilities
+                .get(*entity)
+                .map(|(visibility, _, maybe_hidden_source)| {
+                    maybe_hidden_source
+                        .map(|hidden_source| hidden_source.previous_visibility)
+                        .unwrap_or(*visibility)
+                })
+                .unwrap_or_default();
+            entity_commands.try_insert((
+                Visibility::Hidden,
+                HiddenDespawnSource {
+                    effect_id: *effect_id,
+                    previous_visibility,
+                    despawn: *source_mode == SourceMode::HideAndDespawn,
+                    recurse: *recurse,
+                },
+            ));
         }
+    }
+    true
+}
 
-        let (mesh_handle, maybe_image_params, maybe_color_material) = if let Ok(sprite) =
-            sprites.get(*entity)
-        {
-            let image_handle = &sprite.image;
-            let maybe_texture_atlas = (&sprite.texture_atlas).as_ref();
-            let image_size = images
-                .get(image_handle)
-                .and_then(|image| Some(image.size().as_vec2()))
-                .ok_or(DespawnParticlesError::InvalidImageHandle)?;
-
-            // Get input_size and offset from atlas if it exists, else default to
-            // no offset and the full images size.
-            let (input_size, offset) = maybe_texture_atlas
-                .and_then(|atlas| atlas.texture_rect(&atlas_layouts))
-                .map(|rect| {
-                    (
-                        Vec2::new(rect.width() as f32, rect.height() as f32),
-                        rect.min.as_vec2(),
-                    )
-                })
-                .unwrap_or((image_size, Vec2::ZERO));
-
-            let mesh = Rectangle::new(input_size.x, input_size.y);
-
-            (
-                meshes.add(mesh).into(),
-                Some(ImageParams {
-                    offset,
-                    image_handle: image_handle.clone(),
-                    input_size,
-                    texture_size: image_size,
-                    custom_size: sprite.custom_size,
-                }),
-                None,
-            )
-        } else if let Ok((mesh_handle, maybe_color_material)) = mesh_components.get(*entity) {
-            let base_color = maybe_color_material
-                .and_then(|handle| color_materials.get(handle))
-                .and_then(|material| Some(material.color))
-                .unwrap_or(GRAY.into());
-            let final_color = if gray == 1 {
-                let linear_color = base_color.to_linear();
-                let mixed_shade = linear_color.red * 0.299
-                    + linear_color.green * 0.587
-                    + linear_color.blue * 0.114;
-                LinearRgba::new(mixed_shade, mixed_shade, mixed_shade, linear_color.alpha).into()
-            } else {
-                base_color
-            };
-            (
-                mesh_handle.clone(),
-                None,
-                Some(color_materials.add(ColorMaterial::from(final_color))),
-            )
-        } else {
-            return Err(DespawnParticlesError::EntityMissingComponents);
-        };
+/// The entity that particles are generated from, along with what it looks like, see
+/// [DespawnParticlesParams::prepare_despawn_particles].
+struct PreparedTarget {
+    target: Entity,
+    image_params: Option<ImageParams>,
+    color_material: Option<(Handle<ColorMaterial>, LinearRgba)>,
+}
 
-        // Find which mesh to use.
-        let mesh_handle = event_mesh_override
-            .clone()
-            .or_else(|| {
-                despawn_mesh_overrides
-                    .get(*entity)
-                    .and_then(|c| Ok(c.0.clone()))
-                    .ok()
-            })
-            .unwrap_or(mesh_handle.0);
+/// Breaks the mesh of a [PreparedTarget] into fragments. This only needs the mesh, so it can run
+/// on any thread.
+struct FragmentJob {
+    mesh: Mesh,
+    target_num_particles: usize,
+    split: bool,
+}
 
-        // Break the mesh into smaller triangles
-        let mut mesh = meshes
-            .get(&mesh_handle)
-            .cloned()
-            .ok_or(DespawnParticlesError::InvalidMeshHandle)?;
-        let triangle_meshes = if let PrimitiveTopology::TriangleList = mesh.primitive_topology() {
+impl FragmentJob {
+    /// Returns each fragment re-centered around the origin, along with its offset from the
+    /// original origin.
+    fn run(self) -> Result<Vec<(Mesh, Vec3)>, DespawnParticlesError> {
+        let FragmentJob {
+            mut mesh,
+            target_num_particles,
+            split,
+        } = self;
+        if let PrimitiveTopology::TriangleList = mesh.primitive_topology() {
             let vertices = mesh
                 .attribute(Mesh::ATTRIBUTE_POSITION)
                 .ok_or(DespawnParticlesError::MeshMissingPositionAttribute)
@@ -267,28 +279,33 @@ fn handle_despawn_particles_event(
             }
 
             // Break down the triangles into individual meshes
-            let meshes = split_mesh(mesh, target_num_particles)?;
+            let meshes = if split {
+                split_mesh(mesh, target_num_particles)?
+            } else {
+                vec![mesh]
+            };
 
             // Re-center the triangles around the origin, saving that offset for the
             // Transform
             // We can assume every mesh has TriangleList topology and proper indices.
 
-            meshes
+            Ok(meshes
                 .into_iter()
                 .map(|mut mesh| {
-                    // These unwraps are guaranteed safe due to the call to split_mesh making
-                    // the same check.
-                    let vertices: [[f32; 3]; 3] = mesh
+                    // These unwraps are guaranteed safe due to the call to split_mesh, or the
+                    // check above, making the same check.
+                    let vertices = mesh
                         .attribute(Mesh::ATTRIBUTE_POSITION)
                         .unwrap()
                         .as_float3()
-                        .unwrap()
-                        .try_into()
                         .unwrap();
 
                     // Get the centroid of the triangle, we will use this to translate this
                     // mesh to the origin
-                    let centroid = float32x3_triangle_centroid(vertices);
+                    let centroid = match <[[f32; 3]; 3]>::try_from(vertices) {
+                        Ok(triangle) => float32x3_triangle_centroid(triangle),
+                        Err(_) => float32x3_centroid(vertices),
+                    };
 
                     // Translate the triangle around the origin point using the centroid.
                     // Collect into a Vec since it will be converted to this for the mesh
@@ -301,16 +318,480 @@ fn handle_despawn_particles_event(
                     mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, new_vertices);
                     (mesh, Vec3::from(centroid))
                 })
-                .collect::<Vec<_>>()
+                .collect::<Vec<_>>())
         } else {
             // We do not have a TriangleList mesh format, so we cannot continue.
-            return Err(DespawnParticlesError::UnexpectedMeshTopology);
+            Err(DespawnParticlesError::UnexpectedMeshTopology)
+        }
+    }
+}
+
+/// Everything needed to handle a [DespawnParticlesEvent], shared by the event reader and the
+/// [Commands] extensions.
+#[derive(SystemParam)]
+pub(crate) struct DespawnParticlesParams<'w, 's> {
+    commands: Commands<'w, 's>,
+    images: Res<'w, Assets<Image>>,
+    meshes: ResMut<'w, Assets<Mesh>>,
+    atlas_layouts: Res<'w, Assets<TextureAtlasLayout>>,
+    global_transforms: Query<'w, 's, &'static GlobalTransform>,
+    despawn_materials: ResMut<'w, Assets<DespawnMaterial>>,
+    sprites: Query<'w, 's, &'static Sprite>,
+    mesh_components: Query<
+        'w,
+        's,
+        (
+            &'static Mesh2d,
+            Option<&'static MeshMaterial2d<ColorMaterial>>,
+        ),
+    >,
+    color_materials: ResMut<'w, Assets<ColorMaterial>>,
+    no_death_animations: Query<'w, 's, &'static NoDespawnAnimation>,
+    velocities: Query<'w, 's, &'static Velocity>,
+    despawn_mesh_overrides: Query<'w, 's, &'static DespawnMeshOverride>,
+    despawn_particle_queue: ResMut<'w, DespawnParticleQueue>,
+    budget_queues: ResMut<'w, ParticleBudgetQueues>,
+    config: Res<'w, DespawnParticlesConfig>,
+    pool: ResMut<'w, ParticlePool>,
+    pooled_particles: Query<'w, 's, &'static PooledParticle>,
+    secondary_particle_queue: ResMut<'w, SecondaryParticleQueue>,
+    trail_material: Res<'w, TrailMaterial>,
+    visibilities: Query<
+        'w,
+        's,
+        (
+            &'static Visibility,
+            Option<&'static InheritedVisibility>,
+            Option<&'static HiddenDespawnSource>,
+        ),
+    >,
+    render_layers: Query<'w, 's, &'static RenderLayers>,
+    parents: Query<'w, 's, &'static Parent>,
+    children: Query<'w, 's, &'static Children>,
+    cameras: Query<'w, 's, (&'static Camera, &'static GlobalTransform)>,
+    adaptive_quality: ResMut<'w, AdaptiveQualityState>,
+    active_effects: ResMut<'w, ActiveDespawnEffects>,
+    shatter_on_despawns: Query<'w, 's, (), With<ShatterOnDespawn>>,
+}
+
+impl DespawnParticlesParams<'_, '_> {
+    pub(crate) fn handle_event(&mut self, event: &DespawnParticlesEvent) {
+        self.handle_events([event]);
+    }
+
+    /// Handles a batch of events. The fragments of every entity in the batch are generated in
+    /// parallel on the [ComputeTaskPool], after which the particles are spawned and their assets
+    /// added all at once.
+    pub(crate) fn handle_events<'a>(
+        &mut self,
+        events: impl IntoIterator<Item = &'a DespawnParticlesEvent>,
+    ) {
+        // The same event may be sent more than once, in which case the effect shares its root.
+        let mut roots = HashMap::new();
+        let mut effects = Vec::new();
+        let mut jobs = Vec::new();
+        for event in events {
+            let root = *roots.entry(event.effect_id).or_insert_with(|| {
+                self.active_effects
+                    .0
+                    .get(&event.effect_id)
+                    .copied()
+                    .unwrap_or_else(|| self.commands.spawn_empty().id())
+            });
+
+            // When recursing, every descendant with a sprite or mesh shatters as part of the
+            // effect.
+            let targets = std::iter::once(event.entity)
+                .chain(
+                    event
+                        .recurse
+                        .then(|| self.children.iter_descendants(event.entity))
+                        .into_iter()
+                        .flatten(),
+                )
+                .collect::<Vec<_>>();
+            let exists = apply_source_mode(
+                event,
+                &mut self.commands,
+                &self.visibilities,
+                &self.shatter_on_despawns,
+                &targets,
+            );
+
+            let mut prepared_targets = Vec::new();
+            for target in targets.into_iter().filter(|_| exists) {
+                match self.prepare_despawn_particles(event, target) {
+                    Ok(Some((prepared, job))) => {
+                        prepared_targets.push(prepared);
+                        jobs.push(job);
+                    }
+                    Ok(None) => {}
+                    // Parts of a hierarchy without a sprite or mesh have nothing to shatter.
+                    Err(DespawnParticlesError::EntityMissingComponents) if event.recurse => {}
+                    Err(e) => {
+                        error!(
+                            "Could not create despawn particles for entity {:?}: {}",
+                            target, e
+                        );
+                    }
+                }
+            }
+            effects.push((event, root, prepared_targets));
+        }
+
+        // Splitting the meshes is the bulk of the work, so spread it over the compute threads.
+        let mut triangle_meshes = if jobs.len() > 1 {
+            ComputeTaskPool::get_or_init(TaskPool::default).scope(|scope| {
+                for job in jobs {
+                    scope.spawn(async move { job.run() });
+                }
+            })
+        } else {
+            jobs.into_iter().map(FragmentJob::run).collect()
+        }
+        .into_iter();
+
+        for (event, root, prepared_targets) in effects {
+            // The budget the particles count towards.
+            let (budget_name, eviction) = match event
+                .budget
+                .as_ref()
+                .and_then(|budget| self.config.budgets.get_key_value(budget))
+            {
+                Some((name, budget)) => (Some(name.clone()), budget.eviction),
+                None => {
+                    if let Some(budget) = event.budget.as_ref() {
+                        warn!("No particle budget named {:?}, using max_particles", budget);
+                    }
+                    (None, self.config.eviction)
+                }
+            };
+
+            let mut fragments = Vec::new();
+            for prepared in prepared_targets {
+                let target = prepared.target;
+                match triangle_meshes.next() {
+                    Some(Ok(target_triangle_meshes)) => {
+                        fragments.extend(self.spawn_despawn_particles(
+                            event,
+                            prepared,
+                            target_triangle_meshes,
+                            eviction,
+                            root,
+                        ))
+                    }
+                    Some(Err(e)) => {
+                        error!(
+                            "Could not create despawn particles for entity {:?}: {}",
+                            target, e
+                        );
+                    }
+                    None => {}
+                }
+            }
+            let particle_queue = match budget_name {
+                Some(name) => self.budget_queues.0.entry(name).or_default(),
+                None => &mut self.despawn_particle_queue.0,
+            };
+            particle_queue.extend(fragments.iter().copied());
+
+            // Sparks are spawned once for the whole effect, from the center of the target entity.
+            if let Some(sparks) = event
+                .secondary_emitters
+                .as_ref()
+                .and_then(|emitters| emitters.sparks.as_ref())
+                .filter(|_| !fragments.is_empty())
+            {
+                if let Ok(global_transform) = self.global_transforms.get(event.entity) {
+                    spawn_secondary_particles(
+                        &mut self.commands,
+                        &mut self.secondary_particle_queue,
+                        &Arc::new(sparks.style.clone()),
+                        global_transform.translation(),
+                        sparks.count.get_value(),
+                        particle_visibility(&self.visibilities, event.entity, event.entity),
+                        self.render_layers.get(event.entity).ok(),
+                    );
+                }
+            }
+
+            self.adaptive_quality.fragments_this_frame += fragments.len();
+            track_effect(
+                &mut self.commands,
+                &mut self.active_effects,
+                event,
+                root,
+                fragments,
+            );
+        }
+    }
+
+    /// Looks up the sprite or mesh of a single entity of the event, which is either the target entity
+    /// or, when recursing, one of its descendants. Returns None when the entity should not generate
+    /// any particles.
+    fn prepare_despawn_particles(
+        &mut self,
+        event: &DespawnParticlesEvent,
+        target: Entity,
+    ) -> Result<Option<(PreparedTarget, FragmentJob)>, DespawnParticlesError> {
+        let DespawnParticlesParams {
+            images,
+            meshes,
+            atlas_layouts,
+            global_transforms,
+            sprites,
+            mesh_components,
+            color_materials,
+            no_death_animations,
+            despawn_mesh_overrides,
+            config,
+            cameras,
+            adaptive_quality,
+            ..
+        } = self;
+        let lod = event.lod.as_ref().or(config.lod.as_ref());
+        let quality = adaptive_quality.quality;
+        let DespawnParticlesEvent {
+            mesh_override: event_mesh_override,
+            target_num_particles,
+            gray,
+            dissolve,
+            blend_mode,
+            ..
+        } = event;
+        let target_num_particles =
+            ((target_num_particles.get_value() as f32 * quality).round() as usize).max(1);
+
+        let gray: u32 = gray.then(|| 1).unwrap_or(0); // Need to convert for shader
+
+        // Now spawn the death animation, if possible
+        if no_death_animations.get(target).is_ok() {
+            // We ignore death animations for this object.
+            return Ok(None);
+        }
+
+        let (mesh_handle, maybe_image_params, maybe_color_material) =
+            if let Ok(sprite) = sprites.get(target) {
+                let image_handle = &sprite.image;
+                let maybe_texture_atlas = (&sprite.texture_atlas).as_ref();
+                let image_size = images
+                    .get(image_handle)
+                    .and_then(|image| Some(image.size().as_vec2()))
+                    .ok_or(DespawnParticlesError::InvalidImageHandle)?;
+
+                // Get input_size and offset from atlas if it exists, else default to
+                // no offset and the full images size.
+                let (input_size, offset) = maybe_texture_atlas
+                    .and_then(|atlas| atlas.texture_rect(&atlas_layouts))
+                    .map(|rect| {
+                        (
+                            Vec2::new(rect.width() as f32, rect.height() as f32),
+                            rect.min.as_vec2(),
+                        )
+                    })
+                    .unwrap_or((image_size, Vec2::ZERO));
+
+                let mesh = Rectangle::new(input_size.x, input_size.y);
+
+                (
+                    meshes.add(mesh).into(),
+                    Some(ImageParams {
+                        offset,
+                        image_handle: image_handle.clone(),
+                        input_size,
+                        texture_size: image_size,
+                        custom_size: sprite.custom_size,
+                    }),
+                    None,
+                )
+            } else if let Ok((mesh_handle, maybe_color_material)) = mesh_components.get(target) {
+                let base_color = maybe_color_material
+                    .and_then(|handle| color_materials.get(handle))
+                    .and_then(|material| Some(material.color))
+                    .unwrap_or(GRAY.into());
+                let final_color = if gray == 1 {
+                    let linear_color = base_color.to_linear();
+                    let mixed_shade = linear_color.red * 0.299
+                        + linear_color.green * 0.587
+                        + linear_color.blue * 0.114;
+                    LinearRgba::new(mixed_shade, mixed_shade, mixed_shade, linear_color.alpha)
+                } else {
+                    base_color.to_linear()
+                };
+                (
+                    mesh_handle.clone(),
+                    None,
+                    Some((
+                        color_materials.add(ColorMaterial {
+                            color: final_color.into(),
+                            alpha_mode: blend_mode.color_material_alpha_mode(),
+                            texture: None,
+                        }),
+                        final_color,
+                    )),
+                )
+            } else {
+                return Err(DespawnParticlesError::EntityMissingComponents);
+            };
+
+        // Find which mesh to use.
+        let mesh_handle = event_mesh_override
+            .clone()
+            .or_else(|| {
+                despawn_mesh_overrides
+                    .get(target)
+                    .and_then(|c| Ok(c.0.clone()))
+                    .ok()
+            })
+            .unwrap_or(mesh_handle.0);
+
+        // Break the mesh into smaller triangles
+        let mesh = meshes
+            .get(&mesh_handle)
+            .cloned()
+            .ok_or(DespawnParticlesError::InvalidMeshHandle)?;
+
+        // Scale down the number of particles of entities that are small, far away or off-screen.
+        let target_num_particles = match (lod, global_transforms.get(target), mesh.compute_aabb()) {
+            (Some(lod), Ok(transform), Some(aabb)) => {
+                let scale = maybe_image_params
+                    .as_ref()
+                    .and_then(|params| params.custom_size.map(|size| size / params.input_size))
+                    .unwrap_or(Vec2::ONE);
+                match lod.target_num_particles(
+                    target_num_particles,
+                    aabb,
+                    scale,
+                    transform,
+                    cameras,
+                ) {
+                    Some(target_num_particles) => target_num_particles,
+                    None => return Ok(None),
+                }
+            }
+            _ => target_num_particles,
         };
 
-        if let Ok(orig_transform) = global_transforms.get(*entity) {
+        // Break down the triangles into individual meshes
+        // Unless the whole mesh dissolves as one particle.
+        let split = dissolve.as_ref().is_none_or(|dissolve| dissolve.split);
+
+        Ok(Some((
+            PreparedTarget {
+                target,
+                image_params: maybe_image_params,
+                color_material: maybe_color_material,
+            },
+            FragmentJob {
+                mesh,
+                target_num_particles,
+                split,
+            },
+        )))
+    }
+
+    /// Spawns the particles of a [PreparedTarget] from its fragments, returning them so that they
+    /// can be added to the queue of their budget.
+    fn spawn_despawn_particles(
+        &mut self,
+        event: &DespawnParticlesEvent,
+        prepared: PreparedTarget,
+        triangle_meshes: Vec<(Mesh, Vec3)>,
+        eviction: EvictionPolicy,
+        root: Entity,
+    ) -> Vec<Entity> {
+        let DespawnParticlesParams {
+            commands,
+            images,
+            meshes,
+            global_transforms,
+            despawn_materials,
+            color_materials,
+            velocities,
+            pool,
+            pooled_particles,
+            trail_material,
+            visibilities,
+            render_layers,
+            parents,
+            ..
+        } = self;
+        let DespawnParticlesEvent {
+            entity,
+            effect_id,
+            linvel,
+            linvel_addtl,
+            angvel,
+            ignore_parent_phys,
+            lifetime,
+            linear_damping,
+            angular_damping,
+            mass,
+            z_offset,
+            shrink,
+            fade,
+            fade_curve,
+            shrink_curve,
+            shrink_axes,
+            shrink_pivot,
+            velocity_curve,
+            gray,
+            color_over_lifetime,
+            dissolve,
+            blend_mode,
+            flash,
+            secondary_emitters,
+            trail,
+            simulation_space,
+            ..
+        } = event;
+        let PreparedTarget {
+            target,
+            image_params: maybe_image_params,
+            color_material: maybe_color_material,
+        } = prepared;
+        let gray: u32 = gray.then(|| 1).unwrap_or(0); // Need to convert for shader
+
+        let initial_tint = color_over_lifetime
+            .as_ref()
+            .map(|gradient| gradient.sample(0.0))
+            .unwrap_or(LinearRgba::WHITE);
+
+        let particle_visibility = particle_visibility(visibilities, *entity, target);
+        let render_layers = render_layers.get(target).ok();
+
+        let mut fragments = Vec::new();
+        if let Ok(orig_transform) = global_transforms.get(target) {
             let orig_transform: Transform = (*orig_transform).into();
             let center_point = orig_transform.translation;
 
+            let secondary_emitters = secondary_emitters
+                .as_ref()
+                .map(SharedSecondaryEmitters::from);
+
+            // The entity the particles are simulated relative to, along with its transform and
+            // velocity. Rapier always simulates in world space.
+            let space = match simulation_space {
+                SimulationSpace::World => None,
+                SimulationSpace::SourceParent => parents.get(*entity).ok().map(Parent::get),
+                SimulationSpace::Entity(space) => Some(*space),
+            }
+            .filter(|_| cfg!(not(feature = "bevy_rapier2d")))
+            .and_then(|space| match global_transforms.get(space) {
+                Ok(space_transform) => Some((
+                    space,
+                    space_transform,
+                    velocities.get(space).copied().unwrap_or_default(),
+                )),
+                Err(_) => {
+                    warn!(
+                        "Simulation space {:?} has no GlobalTransform, using world space",
+                        space
+                    );
+                    None
+                }
+            });
+
             // scale to apply to each new mesh
             let scale = orig_transform.scale
                 * maybe_image_params
@@ -332,10 +813,15 @@ fn handle_despawn_particles_event(
                     * orig_transform.scale
                     * addtl_translation.extend(1.0);
                 let angle = angle_between3(center_point, translation);
-                let parent_velocity = velocities.get(*entity).copied().unwrap_or_default();
+                // Parts of a hierarchy move along with the target entity.
+                let parent_velocity = velocities
+                    .get(target)
+                    .or_else(|_| velocities.get(*entity))
+                    .copied()
+                    .unwrap_or_default();
 
                 let particle_transform = Transform {
-                    translation,
+                    translation: translation + Vec3::Z * z_offset.get_value(),
                     rotation: orig_transform.rotation,
                     scale,
                 };
@@ -358,7 +844,97 @@ fn handle_despawn_particles_event(
                     }
                     + linvel_addtl.get_value();
 
-                let mut entity_cmds = commands.spawn((
+                // Move the particle into the space it is simulated in. The velocity becomes
+                // relative to that of the space, since the particle now moves along with it.
+                let (particle_transform, velocity) = match space {
+                    Some((_, space_transform, space_velocity)) => {
+                        let (space_scale, space_rotation, _) =
+                            space_transform.to_scale_rotation_translation();
+                        let local_velocity = (space_rotation.inverse()
+                            * (velocity - space_velocity.linvel).extend(0.0))
+                        .truncate()
+                            / space_scale.truncate();
+                        (
+                            GlobalTransform::from(particle_transform)
+                                .reparented_to(space_transform),
+                            local_velocity,
+                        )
+                    }
+                    None => (particle_transform, velocity),
+                };
+
+                let trail_color = trail.map(|trail| {
+                    trail
+                        .color
+                        .map(|color| color.to_linear())
+                        .or_else(|| {
+                            maybe_image_params.as_ref().and_then(|image_params| {
+                                let image = images.get(&image_params.image_handle)?;
+                                let uvs = match mesh.attribute(Mesh::ATTRIBUTE_UV_0)? {
+                                    VertexAttributeValues::Float32x2(uvs) => uvs,
+                                    _ => return None,
+                                };
+                                average_color(
+                                    image,
+                                    image_params.offset,
+                                    image_params.input_size,
+                                    uvs,
+                                )
+                            })
+                        })
+                        .or_else(|| maybe_color_material.as_ref().map(|(_, color)| *color))
+                        .unwrap_or(LinearRgba::WHITE)
+                });
+
+                let pivot = match shrink_pivot {
+                    ShrinkPivot::Centroid => Vec2::ZERO,
+                    ShrinkPivot::LeadingVertex => mesh
+                        .attribute(Mesh::ATTRIBUTE_POSITION)
+                        .and_then(|vertices| vertices.as_float3())
+                        .and_then(|vertices| {
+                            vertices
+                                .iter()
+                                .map(|vertex| Vec3::from(*vertex))
+                                .max_by(|a, b| {
+                                    let leading = |vertex: &Vec3| {
+                                        (particle_transform.rotation
+                                            * (particle_transform.scale * *vertex))
+                                            .truncate()
+                                            .dot(velocity)
+                                    };
+                                    leading(a).total_cmp(&leading(b))
+                                })
+                        })
+                        .map(|vertex| vertex.truncate())
+                        .unwrap_or(Vec2::ZERO),
+                    ShrinkPivot::Offset(offset) => *offset,
+                };
+
+                let fragment_trail = trail.zip(trail_color).map(|(trail, color)| {
+                    let (ribbon, ribbon_mesh) = spawn_ribbon(
+                        commands,
+                        meshes,
+                        trail_material,
+                        particle_visibility,
+                        render_layers,
+                    );
+                    FragmentTrail::new(Arc::new(trail), color, ribbon, ribbon_mesh)
+                });
+
+                let area =
+                    (eviction == EvictionPolicy::Smallest).then(|| triangle_list_area(&mesh));
+
+                // Reuse a pooled particle along with its assets when possible.
+                let pooled = pool.acquire(pooled_particles);
+                let mesh_handle = match pooled.as_ref() {
+                    Some((_, pooled_particle)) => {
+                        meshes.insert(&pooled_particle.mesh, mesh);
+                        pooled_particle.mesh.clone()
+                    }
+                    None => meshes.add(mesh),
+                };
+
+                let bundle = (
                     DespawnParticleBundle {
                         despawn_particle: DespawnParticle::new(lifetime.get_value()),
                         velocity: Velocity {
@@ -375,82 +951,296 @@ fn handle_despawn_particles_event(
                         mass: AdditionalMassProperties::Mass(mass.get_value()),
                         ..Default::default()
                     },
-                    Mesh2d::from(meshes.add(mesh)),
+                    Mesh2d::from(mesh_handle),
                     particle_transform,
-                    Visibility::default(),
-                ));
+                    particle_visibility,
+                    DespawnParticleEffect {
+                        effect_id: *effect_id,
+                        source: target,
+                        root,
+                    },
+                );
+                let mut entity_cmds = match pooled.as_ref() {
+                    Some((entity, _)) => {
+                        let mut entity_cmds = commands.entity(*entity);
+                        entity_cmds.insert(bundle);
+                        entity_cmds
+                    }
+                    None => commands.spawn(bundle),
+                };
 
                 if let Some(image_params) = maybe_image_params.as_ref() {
                     // We have a texture
-                    let material = despawn_materials.add(DespawnMaterial {
+                    let material = DespawnMaterial {
                         alpha: 1.0,
                         source_image: Some(image_params.image_handle.clone()),
                         offset: (image_params.offset / image_params.texture_size),
                         size: (image_params.input_size / image_params.texture_size),
                         gray,
                         padding: 0,
-                    });
+                        tint: initial_tint,
+                        dissolve: 0.0,
+                        dissolve_edge_width: dissolve
+                            .as_ref()
+                            .map(|dissolve| dissolve.edge_width)
+                            .unwrap_or_default(),
+                        dissolve_noise_scale: dissolve
+                            .as_ref()
+                            .map(|dissolve| dissolve.noise_scale)
+                            .unwrap_or_default(),
+                        dissolve_edge_color: dissolve
+                            .as_ref()
+                            .map(|dissolve| dissolve.edge_color.to_linear())
+                            .unwrap_or_default(),
+                        flash_color: flash
+                            .map(|flash| flash.color.to_linear())
+                            .unwrap_or_default(),
+                        flash: flash.map(|_| 1.0).unwrap_or(0.0),
+                        blend_mode_index: 0,
+                        alpha_cutoff: 0.0,
+                        blend_mode: DespawnBlendMode::Blend,
+                    }
+                    .with_blend_mode(*blend_mode);
+                    let material = match pooled.as_ref() {
+                        Some((_, pooled_particle)) => {
+                            despawn_materials.insert(&pooled_particle.despawn_material, material);
+                            pooled_particle.despawn_material.clone()
+                        }
+                        None => despawn_materials.add(material),
+                    };
                     entity_cmds.insert(MeshMaterial2d(material));
-                } else if let Some(color_material_handle) = maybe_color_material.clone() {
-                    // We have no texture, just use color materials
-                    entity_cmds.insert(MeshMaterial2d(color_material_handle.clone()));
-                    entity_cmds.insert(OriginalAlpha(
-                        color_materials
-                            .get(&color_material_handle)
-                            .and_then(|material| Some(material.color.alpha()))
-                            .unwrap_or(1.0),
+                } else if let Some((color_material_handle, original_color)) =
+                    maybe_color_material.as_ref()
+                {
+                    // We have no texture, just use color materials. Particles that change color
+                    // over their lifetime each need their own material.
+                    let color_material_handle =
+                        if *fade || color_over_lifetime.is_some() || flash.is_some() {
+                            let material = ColorMaterial {
+                                color: multiply(*original_color, initial_tint).into(),
+                                alpha_mode: blend_mode.color_material_alpha_mode(),
+                                texture: None,
+                            };
+                            match pooled.as_ref() {
+                                Some((_, pooled_particle)) => {
+                                    color_materials
+                                        .insert(&pooled_particle.color_material, material);
+                                    pooled_particle.color_material.clone()
+                                }
+                                None => color_materials.add(material),
+                            }
+                        } else {
+                            color_material_handle.clone()
+                        };
+                    entity_cmds.insert((
+                        MeshMaterial2d(color_material_handle),
+                        OriginalColor(*original_color),
                     ));
                 }
 
-                shrink_spawn_func(&mut entity_cmds);
-                fade_spawn_func(&mut entity_cmds);
+                if let Some(gradient) = color_over_lifetime.as_ref() {
+                    entity_cmds.insert(ColorOverLifetime(gradient.clone()));
+                }
+                if let Some(dissolve) = dissolve.as_ref() {
+                    entity_cmds.insert(DissolvingDespawnParticle(dissolve.curve.clone()));
+                }
+                if let Some(flash) = flash {
+                    entity_cmds.insert(FlashingDespawnParticle {
+                        color: flash.color.to_linear(),
+                        duration: flash.duration,
+                    });
+                }
+
+                if *shrink {
+                    // The scale of the transform, which is relative to the simulation space.
+                    entity_cmds.insert(ShrinkingDespawnParticle {
+                        curve: shrink_curve.clone(),
+                        initial_scale: particle_transform.scale,
+                        axes: *shrink_axes,
+                        pivot,
+                    });
+                }
+                if *fade {
+                    entity_cmds.insert(FadingDespawnParticle(fade_curve.clone()));
+                }
+                if let Some(fragment_trail) = fragment_trail {
+                    entity_cmds.insert(fragment_trail);
+                }
+                if let Some(emitters) = secondary_emitters.as_ref() {
+                    if let Some(dust) = emitters.dust.as_ref() {
+                        entity_cmds.insert(DustOnLanding::new(dust));
+                    }
+                    if let Some(smoke) = emitters.smoke.as_ref() {
+                        entity_cmds.insert(SmokeTrail::new(smoke));
+                    }
+                }
+                if let Some(curve) = velocity_curve.as_ref() {
+                    entity_cmds.insert(VelocityOverLifetime {
+                        curve: curve.clone(),
+                        previous: 1.0,
+                    });
+                }
+
+                if let Some(render_layers) = render_layers {
+                    entity_cmds.insert(render_layers.clone());
+                }
+                if let Some((space, ..)) = space {
+                    entity_cmds.set_parent(space);
+                }
+                if let Some(area) = area {
+                    entity_cmds.insert(FragmentArea(area));
+                }
 
-                despawn_particle_queue.0.push_back(entity_cmds.id());
+                fragments.push(entity_cmds.id());
             }
         }
+        fragments
     }
-    Ok(())
 }
 
 /// Spawns death particles by creating a particles with a shader that pulls a small portion of the original texture
 pub(crate) fn handle_despawn_particles_events(
-    mut commands: Commands,
-    images: Res<Assets<Image>>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    atlas_layouts: Res<Assets<TextureAtlasLayout>>,
-    global_transforms: Query<&GlobalTransform>,
-    mut despawn_materials: ResMut<Assets<DespawnMaterial>>,
-    sprites: Query<&Sprite>,
-    mesh_components: Query<(&Mesh2d, Option<&MeshMaterial2d<ColorMaterial>>)>,
-    mut color_materials: ResMut<Assets<ColorMaterial>>,
     mut despawn_particles_event_reader: EventReader<DespawnParticlesEvent>,
-    no_death_animations: Query<&NoDespawnAnimation>,
-    velocities: Query<&Velocity>,
-    despawn_mesh_overrides: Query<&DespawnMeshOverride>,
-    mut despawn_particle_queue: ResMut<DespawnParticleQueue>,
+    mut params: DespawnParticlesParams,
 ) {
-    for event in despawn_particles_event_reader.read() {
-        if let Err(e) = handle_despawn_particles_event(
-            event,
-            &mut commands,
-            &images,
-            &mut meshes,
-            &atlas_layouts,
-            &global_transforms,
-            &mut despawn_materials,
-            &sprites,
-            &mesh_components,
-            &mut color_materials,
-            &no_death_animations,
-            &velocities,
-            &despawn_mesh_overrides,
-            &mut despawn_particle_queue,
-        ) {
-            error!(
-                "Could not create despawn particles for entity {:?}: {}",
-                event.entity, e
-            );
+    let deferred = std::mem::take(&mut params.adaptive_quality.deferred);
+    let Some(adaptive_quality) = params.config.adaptive_quality.clone() else {
+        let deferred = deferred
+            .into_iter()
+            .map(|(event, _)| event)
+            .collect::<Vec<_>>();
+        params.handle_events(deferred.iter().chain(despawn_particles_event_reader.read()));
+        return;
+    };
+
+    // Events deferred from previous frames go first. The budget is checked in between batches,
+    // each of which is about one event per compute thread.
+    let was_deferring = !deferred.is_empty();
+    let mut pending = deferred
+        .into_iter()
+        .chain(
+            despawn_particles_event_reader
+                .read()
+                .map(|event| (event.clone(), 0)),
+        )
+        .collect::<VecDeque<_>>();
+    let batch_size = ComputeTaskPool::get_or_init(TaskPool::default)
+        .thread_num()
+        .max(1);
+    while !pending.is_empty() {
+        let has_budget = params.adaptive_quality.has_budget(&adaptive_quality);
+        let mut batch = Vec::new();
+        while batch.len() < batch_size {
+            let Some((event, frames)) = pending.pop_front() else {
+                break;
+            };
+            if has_budget || frames >= adaptive_quality.max_deferred_frames {
+                batch.push(event);
+            } else {
+                params
+                    .adaptive_quality
+                    .deferred
+                    .push_back((event, frames + 1));
+            }
+        }
+        params.handle_events(&batch);
+    }
+    if !was_deferring && !params.adaptive_quality.deferred.is_empty() {
+        warn!(
+            "Over the fragment budget, deferring {} despawn particle events",
+            params.adaptive_quality.deferred.len()
+        );
+    }
+}
+
+/// Handles a single [DespawnParticlesEvent] right away, used by the [Commands] extensions.
+pub(crate) fn handle_despawn_particles_command(
+    In(event): In<DespawnParticlesEvent>,
+    mut params: DespawnParticlesParams,
+) {
+    params.handle_event(&event);
+}
+
+/// Starts tracking the particles spawned for an effect, finishing it right away if there are none.
+fn track_effect(
+    commands: &mut Commands,
+    active_effects: &mut ActiveDespawnEffects,
+    event: &DespawnParticlesEvent,
+    root: Entity,
+    fragments: Vec<Entity>,
+) {
+    if !fragments.is_empty() {
+        if let Some(on_spawn) = event.on_spawn.as_ref() {
+            (on_spawn.0)(commands, &fragments);
+        }
+    }
+
+    let effect_id = event.effect_id;
+    let source = event.entity;
+    match active_effects.0.entry(effect_id) {
+        Entry::Occupied(_) => {
+            // The same event was sent more than once, so this effect is still in progress.
+            commands.queue(move |world: &mut World| {
+                if let Some(mut effect_root) = world.get_mut::<DespawnParticlesEffectRoot>(root) {
+                    effect_root.fragments.extend(fragments);
+                }
+            });
+        }
+        Entry::Vacant(entry) if !fragments.is_empty() => {
+            entry.insert(root);
+            commands.entity(root).insert(DespawnParticlesEffectRoot {
+                effect_id,
+                source,
+                tag: event.tag.clone(),
+                fragments,
+            });
+        }
+        Entry::Vacant(_) => {
+            commands.entity(root).despawn();
+            finish_effect(commands, DespawnParticlesFinished { source, effect_id });
+        }
+    }
+}
+
+fn finish_effect(commands: &mut Commands, finished: DespawnParticlesFinished) {
+    commands.send_event(finished);
+    commands.trigger(finished);
+}
+
+/// Keeps track of the remaining particles of each effect, finishing the effect once the last one
+/// is removed.
+pub(crate) fn on_despawn_particle_effect_removed(
+    trigger: Trigger<OnRemove, DespawnParticleEffect>,
+    particle_effects: Query<&DespawnParticleEffect>,
+    mut effect_roots: Query<&mut DespawnParticlesEffectRoot>,
+    mut active_effects: ResMut<ActiveDespawnEffects>,
+    mut commands: Commands,
+) {
+    let Ok(&DespawnParticleEffect {
+        effect_id,
+        source,
+        root,
+    }) = particle_effects.get(trigger.entity())
+    else {
+        return;
+    };
+    let finished = if let Ok(mut effect_root) = effect_roots.get_mut(root) {
+        effect_root
+            .fragments
+            .retain(|fragment| *fragment != trigger.entity());
+        effect_root.fragments.is_empty()
+    } else {
+        // The root was despawned by something else, there is nothing left to track.
+        true
+    };
+    if finished && active_effects.0.remove(&effect_id).is_some() {
+        if let Some(mut entity_commands) = commands.get_entity(root) {
+            entity_commands.despawn();
         }
+        finish_effect(
+            &mut commands,
+            DespawnParticlesFinished { source, effect_id },
+        );
     }
 }
 pub(crate) fn handle_despawn_particle(
@@ -458,12 +1248,17 @@ pub(crate) fn handle_despawn_particle(
         Entity,
         AnyOf<(
             &MeshMaterial2d<DespawnMaterial>,
-            (&MeshMaterial2d<ColorMaterial>, &OriginalAlpha),
+            (&MeshMaterial2d<ColorMaterial>, &OriginalColor),
         )>,
         &mut DespawnParticle,
         &mut Transform,
         Option<&ShrinkingDespawnParticle>,
         Option<&FadingDespawnParticle>,
+        Option<&ColorOverLifetime>,
+        Option<&DissolvingDespawnParticle>,
+        Option<&FlashingDespawnParticle>,
+        Option<(&mut Velocity, &mut VelocityOverLifetime)>,
+        Option<&mut EvictedDespawnParticle>,
     )>,
     mut despawn_materials: ResMut<Assets<DespawnMaterial>>,
     mut color_materials: ResMut<Assets<ColorMaterial>>,
@@ -472,37 +1267,161 @@ pub(crate) fn handle_despawn_particle(
 ) {
     for (
         entity,
-        (maybe_despawn_material_handle, maybe_color_material_handle_and_alpha),
+        (maybe_despawn_material_handle, maybe_color_material_handle_and_color),
         mut despawn_particle,
         mut transform,
         maybe_shrink,
         maybe_fade,
+        maybe_color_over_lifetime,
+        maybe_dissolve,
+        maybe_flash,
+        maybe_velocity_over_lifetime,
+        mut maybe_evicted,
     ) in despawn_particles.iter_mut()
     {
-        despawn_particle.lifetime.tick(time.delta());
-        if despawn_particle.lifetime.finished() {
-            if let Some(mut entity_commands) = commands.get_entity(entity) {
-                entity_commands.despawn();
-            }
-        }
-        let percent = despawn_particle.lifetime.fraction_remaining();
-        if let Some(despawn_material) = maybe_fade
-            .and_then(|_| maybe_despawn_material_handle)
-            .and_then(|handle| despawn_materials.get_mut(handle))
+        let speed = maybe_evicted
+            .as_mut()
+            .map(|evicted| {
+                evicted.timer.tick(time.delta());
+                evicted.speed
+            })
+            .unwrap_or(1.0);
+        despawn_particle.lifetime.tick(time.delta().mul_f32(speed));
+        if despawn_particle.lifetime.finished()
+            || maybe_evicted
+                .as_ref()
+                .is_some_and(|evicted| evicted.timer.finished())
         {
-            despawn_material.alpha = percent;
-        } else if let Some((color_material, original_alpha)) = maybe_color_material_handle_and_alpha
-            .and_then(|(handle, a)| color_materials.get_mut(handle).zip(Some(a)))
+            release_particle(&mut commands, entity);
+        }
+        let age = despawn_particle.lifetime.fraction();
+        // Evicted particles fade and shrink away on top of everything else.
+        let eviction_factor = maybe_evicted
+            .as_ref()
+            .map(|evicted| 1.0 - evicted.timer.fraction());
+        if maybe_fade.is_some()
+            || maybe_color_over_lifetime.is_some()
+            || maybe_dissolve.is_some()
+            || maybe_flash.is_some()
+            || eviction_factor.is_some()
         {
-            let orig_color = color_material.color.to_linear();
-            color_material.color = LinearRgba {
-                alpha: original_alpha.0 * percent,
-                ..orig_color
+            let alpha = maybe_fade.map(|fade| fade.0.sample(age)).unwrap_or(1.0)
+                * eviction_factor.unwrap_or(1.0);
+            let tint = maybe_color_over_lifetime
+                .map(|color_over_lifetime| color_over_lifetime.0.sample(age))
+                .unwrap_or(LinearRgba::WHITE);
+            let (flash_color, flash) = maybe_flash
+                .map(|flash| {
+                    (
+                        flash.color,
+                        flash.amount(despawn_particle.lifetime.elapsed_secs()),
+                    )
+                })
+                .unwrap_or_default();
+            if let Some(despawn_material) =
+                maybe_despawn_material_handle.and_then(|handle| despawn_materials.get_mut(handle))
+            {
+                despawn_material.alpha = alpha;
+                despawn_material.tint = tint;
+                despawn_material.flash = flash;
+                if let Some(dissolve) = maybe_dissolve {
+                    despawn_material.dissolve = 1.0 - dissolve.0.sample(age);
+                }
+            } else if let Some((color_material, original_color)) =
+                maybe_color_material_handle_and_color
+                    .and_then(|(handle, c)| color_materials.get_mut(handle).zip(Some(c)))
+            {
+                let color = multiply(original_color.0, tint);
+                let color = color.mix(&flash_color.with_alpha(color.alpha), flash);
+                color_material.color = color.with_alpha(color.alpha * alpha).into();
+            }
+        }
+        if let Some(shrink) = maybe_shrink {
+            let factor = Vec2::ONE - (1.0 - shrink.curve.sample(age)) * shrink.axes;
+            let scale = shrink.initial_scale * factor.extend(1.0);
+            // Keep the pivot in place by moving the particle towards it as much as it shrunk.
+            let pivot_delta = (transform.scale - scale) * shrink.pivot.extend(0.0);
+            let translation_delta = transform.rotation * pivot_delta;
+            transform.translation += translation_delta;
+            transform.scale = scale;
+        }
+        if let Some((evicted, factor)) = maybe_evicted.as_ref().zip(eviction_factor) {
+            let scale = if maybe_shrink.is_some() {
+                transform.scale
+            } else {
+                evicted.scale
+            };
+            transform.scale = scale * factor;
+        }
+        if let Some((mut velocity, mut velocity_over_lifetime)) = maybe_velocity_over_lifetime {
+            // Once the multiplier reaches 0 the velocity can't be recovered, so it stays stopped.
+            let multiplier = velocity_over_lifetime.curve.sample(age);
+            if velocity_over_lifetime.previous > f32::EPSILON {
+                let ratio = multiplier / velocity_over_lifetime.previous;
+                velocity.linvel *= ratio;
+                velocity.angvel *= ratio;
+                velocity_over_lifetime.previous = multiplier;
+            }
+        }
+    }
+}
+
+/// Generates particles for entities with [ShatterOnDespawn] as they are despawned.
+pub(crate) fn on_shatter_on_despawn_removed(
+    trigger: Trigger<OnRemove, ShatterOnDespawn>,
+    shatter_on_despawns: Query<&ShatterOnDespawn, Without<ShatterOnDespawnHandled>>,
+    mut params: DespawnParticlesParams,
+) {
+    if let Ok(ShatterOnDespawn(preset)) = shatter_on_despawns.get(trigger.entity()) {
+        // Whatever removed the component is responsible for the entity, so leave it alone.
+        let event = preset
+            .clone()
+            .with_source_mode(SourceMode::Keep)
+            .build(trigger.entity());
+        params.handle_event(&event);
+    }
+}
+
+/// Restores or despawns entities hidden by a [DespawnParticlesEvent] once their effect has
+/// finished.
+pub(crate) fn handle_hidden_despawn_sources(
+    mut finished_reader: EventReader<DespawnParticlesFinished>,
+    mut hidden_sources: Query<(&HiddenDespawnSource, &mut Visibility)>,
+    shatter_on_despawns: Query<(), With<ShatterOnDespawn>>,
+    children: Query<&Children>,
+    mut commands: Commands,
+) {
+    for DespawnParticlesFinished { source, effect_id } in finished_reader.read() {
+        let Ok((hidden_source, mut visibility)) = hidden_sources.get_mut(*source) else {
+            continue;
+        };
+        // The entity may have been hidden again by a more recent effect.
+        if hidden_source.effect_id != *effect_id {
+            continue;
+        }
+        if !hidden_source.despawn {
+            *visibility = hidden_source.previous_visibility;
+            commands.entity(*source).remove::<HiddenDespawnSource>();
+            continue;
+        }
+        // The particles of the entity, and of its descendants when recursing, were already
+        // generated when it was hidden.
+        let targets = std::iter::once(*source).chain(
+            hidden_source
+                .recurse
+                .then(|| children.iter_descendants(*source))
+                .into_iter()
+                .flatten(),
+        );
+        for target in targets {
+            if shatter_on_despawns.contains(target) {
+                commands.entity(target).try_insert(ShatterOnDespawnHandled);
             }
-            .into();
         }
-        if maybe_shrink.is_some() {
-            transform.scale = Vec3::splat(percent);
+        if hidden_source.recurse {
+            commands.entity(*source).despawn_recursive();
+        } else {
+            commands.entity(*source).despawn();
         }
     }
 }
@@ -510,26 +1429,151 @@ pub(crate) fn handle_despawn_particle(
 pub fn max_particles_check(
     config: Res<DespawnParticlesConfig>,
     mut particle_queue: ResMut<DespawnParticleQueue>,
-    particles: Query<(Entity, &DespawnParticle)>,
+    mut budget_queues: ResMut<ParticleBudgetQueues>,
+    particles: Query<EvictionQueryData>,
+    cameras: Query<&GlobalTransform, With<Camera>>,
+    mut color_materials: ResMut<Assets<ColorMaterial>>,
     mut commands: Commands,
 ) {
-    for _ in 0..(particle_queue.0.len().saturating_sub(config.max_particles)) {
-        particle_queue
-            .0
-            .pop_front()
-            .and_then(|curr_entity| {
-                if particles.contains(curr_entity) {
-                    // We've exceeded the max particles and this particle still exists, so despawn it.
-                    Some(curr_entity)
-                } else {
-                    None
-                }
-            })
-            .and_then(|curr_entity| commands.get_entity(curr_entity))
-            .and_then(|mut entity_cmds| {
-                entity_cmds.despawn();
-                Some(())
-            });
+    let default_budget = ParticleBudget {
+        max_particles: config.max_particles,
+        eviction: config.eviction,
+        eviction_duration: config.eviction_duration,
+    };
+    evict_particles(
+        &mut particle_queue.0,
+        &default_budget,
+        &particles,
+        &cameras,
+        &mut color_materials,
+        &mut commands,
+    );
+    for (name, queue) in budget_queues.0.iter_mut() {
+        if let Some(budget) = config.budgets.get(name) {
+            evict_particles(
+                queue,
+                budget,
+                &particles,
+                &cameras,
+                &mut color_materials,
+                &mut commands,
+            );
+        }
+    }
+}
+
+/// The components of a particle needed to evict it.
+type EvictionQueryData = (
+    &'static DespawnParticle,
+    &'static GlobalTransform,
+    &'static Transform,
+    Option<&'static FragmentArea>,
+    Option<&'static MeshMaterial2d<ColorMaterial>>,
+);
+
+/// Evicts particles from the queue until there are at most
+/// [max_particles][ParticleBudget::max_particles] of the budget left in it.
+fn evict_particles(
+    queue: &mut VecDeque<Entity>,
+    &ParticleBudget {
+        max_particles,
+        eviction,
+        eviction_duration,
+    }: &ParticleBudget,
+    particles: &Query<EvictionQueryData>,
+    cameras: &Query<&GlobalTransform, With<Camera>>,
+    color_materials: &mut Assets<ColorMaterial>,
+    commands: &mut Commands,
+) {
+    if queue.len() <= max_particles {
+        return;
+    }
+    // Forget about the particles that already expired so they don't count.
+    queue.retain(|entity| particles.contains(*entity));
+    let excess = queue.len().saturating_sub(max_particles);
+    if excess == 0 {
+        return;
+    }
+
+    // Evicts the particles with the lowest keys, keeping the rest in order.
+    let mut evict_lowest = |key: &dyn Fn(Entity) -> f32| {
+        let mut keys = queue
+            .iter()
+            .enumerate()
+            .map(|(idx, entity)| (key(*entity), idx))
+            .collect::<Vec<_>>();
+        keys.select_nth_unstable_by(excess - 1, |(a, _), (b, _)| a.total_cmp(b));
+        let mut evicted = vec![false; queue.len()];
+        for (_, idx) in &keys[..excess] {
+            evicted[*idx] = true;
+        }
+        let mut idx = 0;
+        let mut evicted_entities = Vec::with_capacity(excess);
+        queue.retain(|entity| {
+            let keep = !evicted[idx];
+            idx += 1;
+            if !keep {
+                evicted_entities.push(*entity);
+            }
+            keep
+        });
+        evicted_entities
+    };
+
+    let evicted = match eviction {
+        EvictionPolicy::FurthestFromCamera if !cameras.is_empty() => evict_lowest(&|entity| {
+            let translation = particles
+                .get(entity)
+                .map(|(_, global_transform, ..)| global_transform.translation().truncate())
+                .unwrap_or_default();
+            // Negated, so the furthest particles have the lowest keys.
+            -cameras
+                .iter()
+                .map(|camera| camera.translation().truncate().distance(translation))
+                .fold(f32::INFINITY, f32::min)
+        }),
+        EvictionPolicy::Smallest => evict_lowest(&|entity| {
+            particles
+                .get(entity)
+                .ok()
+                .and_then(|(_, global_transform, _, area, _)| {
+                    let scale = global_transform.scale();
+                    area.map(|area| area.0 * (scale.x * scale.y).abs())
+                })
+                .unwrap_or_default()
+        }),
+        _ => queue.drain(..excess).collect(),
+    };
+
+    let eviction_duration = match eviction {
+        EvictionPolicy::FadeOut if eviction_duration <= 0.0 => DEFAULT_EVICTION_DURATION,
+        _ => eviction_duration,
+    };
+    for entity in evicted {
+        if eviction_duration <= 0.0 {
+            release_particle(commands, entity);
+            continue;
+        }
+        let Ok((despawn_particle, _, transform, _, color_material)) = particles.get(entity) else {
+            continue;
+        };
+        // Run out the rest of the lifetime along with the eviction. The particle may expire this
+        // frame, before this is applied.
+        let remaining = despawn_particle.lifetime.remaining_secs();
+        let mut entity_commands = commands.entity(entity);
+        entity_commands.try_insert(EvictedDespawnParticle {
+            timer: Timer::from_seconds(eviction_duration, TimerMode::Once),
+            speed: remaining / eviction_duration,
+            scale: transform.scale,
+        });
+        // Color materials may be shared by the particles of an effect, so the particle needs its
+        // own to fade away on its own.
+        if let Some(material) = color_material
+            .and_then(|handle| color_materials.get(handle))
+            .cloned()
+        {
+            entity_commands.try_insert(MeshMaterial2d(color_materials.add(material)));
+        }
     }
 }
 
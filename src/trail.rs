@@ -0,0 +1,273 @@
+//! Ribbons that trail behind fragments as they move.
+use std::{collections::VecDeque, sync::Arc};
+
+use bevy_asset::{Assets, Handle};
+use bevy_color::{Alpha, Color, ColorToComponents, LinearRgba};
+use bevy_ecs::{
+    component::Component,
+    entity::Entity,
+    observer::Trigger,
+    system::{Commands, Query, Res, ResMut, Resource},
+    world::OnRemove,
+};
+use bevy_image::Image;
+use bevy_math::{Vec2, Vec3};
+use bevy_render::{
+    mesh::{Indices, Mesh, Mesh2d, PrimitiveTopology},
+    render_asset::RenderAssetUsages,
+    view::{RenderLayers, Visibility},
+};
+use bevy_sprite::{AlphaMode2d, ColorMaterial, MeshMaterial2d};
+use bevy_time::Time;
+use bevy_transform::components::{GlobalTransform, Transform};
+use serde::{Deserialize, Serialize};
+
+/// The most points a trail can have, regardless of [Trail::length].
+pub const MAX_TRAIL_POINTS: usize = 64;
+
+/// Renders a fading ribbon behind each fragment, see
+/// [DespawnParticlesEvent::trail][crate::events::DespawnParticlesEvent::trail].
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Trail {
+    /// The number of recent positions that make up the ribbon, up to [MAX_TRAIL_POINTS].
+    pub length: usize,
+
+    /// How long each position stays in the ribbon, in seconds.
+    pub point_lifetime: f32,
+
+    /// The width of the ribbon at the fragment, it tapers off towards the end.
+    pub width: f32,
+
+    /// The color of the ribbon. When not set, the average color of the fragment is used.
+    pub color: Option<Color>,
+}
+
+impl Default for Trail {
+    fn default() -> Self {
+        Self {
+            length: 12,
+            point_lifetime: 0.2,
+            width: 4.0,
+            color: None,
+        }
+    }
+}
+
+impl Trail {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// See [Trail::length]
+    pub fn with_length(mut self, length: usize) -> Self {
+        self.length = length;
+        self
+    }
+
+    /// See [Trail::point_lifetime]
+    pub fn with_point_lifetime(mut self, point_lifetime: f32) -> Self {
+        self.point_lifetime = point_lifetime;
+        self
+    }
+
+    /// See [Trail::width]
+    pub fn with_width(mut self, width: f32) -> Self {
+        self.width = width;
+        self
+    }
+
+    /// See [Trail::color]
+    pub fn with_color<T: Into<Color>>(mut self, color: T) -> Self {
+        self.color = Some(color.into());
+        self
+    }
+}
+
+/// The material shared by every trail, which colors them through their vertex colors.
+#[derive(Resource, Default)]
+pub(crate) struct TrailMaterial(pub Handle<ColorMaterial>);
+
+/// Records the recent positions of a fragment and the entity rendering them as a ribbon.
+#[derive(Component)]
+pub(crate) struct FragmentTrail {
+    trail: Arc<Trail>,
+    color: LinearRgba,
+    ribbon: Entity,
+    mesh: Handle<Mesh>,
+
+    /// The position and age of each point, newest first.
+    points: VecDeque<(Vec3, f32)>,
+}
+
+impl FragmentTrail {
+    pub fn new(trail: Arc<Trail>, color: LinearRgba, ribbon: Entity, mesh: Handle<Mesh>) -> Self {
+        let capacity = trail.length.min(MAX_TRAIL_POINTS);
+        Self {
+            trail,
+            color,
+            ribbon,
+            mesh,
+            points: VecDeque::with_capacity(capacity),
+        }
+    }
+}
+
+pub(crate) fn setup_trails(
+    mut trail_material: ResMut<TrailMaterial>,
+    mut color_materials: ResMut<Assets<ColorMaterial>>,
+) {
+    trail_material.0 = color_materials.add(ColorMaterial {
+        color: Color::WHITE,
+        alpha_mode: AlphaMode2d::Blend,
+        texture: None,
+    });
+}
+
+/// Spawns the entity rendering the ribbon of a trail, returning it along with its mesh.
+pub(crate) fn spawn_ribbon(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    trail_material: &TrailMaterial,
+    visibility: Visibility,
+    render_layers: Option<&RenderLayers>,
+) -> (Entity, Handle<Mesh>) {
+    let mesh = meshes.add(ribbon_mesh(
+        &VecDeque::new(),
+        &Trail::default(),
+        LinearRgba::NONE,
+    ));
+    let mut ribbon = commands.spawn((
+        Mesh2d(mesh.clone()),
+        MeshMaterial2d(trail_material.0.clone()),
+        Transform::default(),
+        visibility,
+    ));
+    if let Some(render_layers) = render_layers {
+        ribbon.insert(render_layers.clone());
+    }
+    (ribbon.id(), mesh)
+}
+
+/// The average color of the texture under the given uvs of a fragment, weighted by alpha.
+///
+/// `offset` and `size` are the section of the image the uvs are relative to, in pixels.
+pub(crate) fn average_color(
+    image: &Image,
+    offset: Vec2,
+    size: Vec2,
+    uvs: &[[f32; 2]],
+) -> Option<LinearRgba> {
+    // Sample each uv, along with points between them and their center, so small fragments
+    // don't get the color of a single pixel.
+    let center =
+        uvs.iter().fold(Vec2::ZERO, |acc, uv| acc + Vec2::from(*uv)) / uvs.len().max(1) as f32;
+    let (sum, weight) = uvs
+        .iter()
+        .flat_map(|uv| {
+            let uv = Vec2::from(*uv);
+            [uv, uv.lerp(center, 0.5)]
+        })
+        .chain(std::iter::once(center))
+        .filter_map(|uv| {
+            let pixel = (offset + uv * size).as_uvec2();
+            image
+                .get_color_at(pixel.x, pixel.y)
+                .ok()
+                .map(|color| color.to_linear())
+        })
+        .fold((Vec3::ZERO, 0.0), |(sum, weight), color| {
+            (sum + color.to_vec3() * color.alpha, weight + color.alpha)
+        });
+    (weight > f32::EPSILON).then(|| LinearRgba::from_vec3(sum / weight))
+}
+
+/// Records the positions of fragments and rebuilds the meshes of their trails.
+pub(crate) fn update_trails(
+    mut trails: Query<(&GlobalTransform, &mut FragmentTrail)>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    time: Res<Time>,
+) {
+    let delta = time.delta_secs();
+    for (global_transform, mut fragment_trail) in trails.iter_mut() {
+        let FragmentTrail {
+            trail,
+            color,
+            mesh,
+            points,
+            ..
+        } = &mut *fragment_trail;
+
+        for (_, age) in points.iter_mut() {
+            *age += delta;
+        }
+        while points
+            .back()
+            .is_some_and(|(_, age)| *age > trail.point_lifetime)
+        {
+            points.pop_back();
+        }
+        points.push_front((global_transform.translation(), 0.0));
+        points.truncate(trail.length.clamp(2, MAX_TRAIL_POINTS));
+
+        if let Some(mesh) = meshes.get_mut(mesh) {
+            *mesh = ribbon_mesh(points, trail, *color);
+        }
+    }
+}
+
+/// Despawns the ribbon of a trail along with its fragment.
+pub(crate) fn on_fragment_trail_removed(
+    trigger: Trigger<OnRemove, FragmentTrail>,
+    trails: Query<&FragmentTrail>,
+    mut commands: Commands,
+) {
+    if let Ok(fragment_trail) = trails.get(trigger.entity()) {
+        if let Some(mut entity_commands) = commands.get_entity(fragment_trail.ribbon) {
+            entity_commands.despawn();
+        }
+    }
+}
+
+/// Builds a ribbon through the given points, which tapers and fades towards the end.
+fn ribbon_mesh(points: &VecDeque<(Vec3, f32)>, trail: &Trail, color: LinearRgba) -> Mesh {
+    let mut positions = Vec::with_capacity(points.len() * 2);
+    let mut colors = Vec::with_capacity(points.len() * 2);
+    let mut indices = Vec::with_capacity(points.len().saturating_sub(1) * 6);
+
+    if points.len() < 2 {
+        // Nothing to draw yet, use a degenerate triangle so the mesh is still valid.
+        positions.extend([[0.0; 3]; 3]);
+        colors.extend([[0.0; 4]; 3]);
+        indices.extend([0, 1, 2]);
+    } else {
+        let count = points.len();
+        for (idx, (point, age)) in points.iter().enumerate() {
+            // The direction of the ribbon at this point, using its neighbours.
+            let previous = points[idx.saturating_sub(1)].0;
+            let next = points[(idx + 1).min(count - 1)].0;
+            let direction = (previous - next).truncate().normalize_or_zero();
+            let taper = 1.0 - idx as f32 / (count - 1) as f32;
+            let offset = direction.perp() * trail.width * 0.5 * taper;
+            positions.push([point.x + offset.x, point.y + offset.y, point.z]);
+            positions.push([point.x - offset.x, point.y - offset.y, point.z]);
+
+            let fade = (1.0 - age / trail.point_lifetime.max(f32::EPSILON)).clamp(0.0, 1.0);
+            let vertex_color = color.with_alpha(color.alpha * fade * taper).to_f32_array();
+            colors.extend([vertex_color, vertex_color]);
+
+            if idx + 1 < count {
+                let idx = idx as u32 * 2;
+                indices.extend([idx, idx + 1, idx + 2, idx + 1, idx + 3, idx + 2]);
+            }
+        }
+    }
+
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_COLOR, colors)
+    .with_inserted_indices(Indices::U32(indices))
+}
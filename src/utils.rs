@@ -31,11 +31,43 @@ pub fn float32x3_triangle_centroid(tri: [[f32; 3]; 3]) -> [f32; 3] {
     //(0..3).map(|idx| (tri[0][idx] + tri[1][idx] + tri[2][idx]) / 3.0).collect()
 }
 
+/// The average of all the given vertices, used for meshes that were not split into triangles.
+#[inline(always)]
+pub fn float32x3_centroid(vertices: &[[f32; 3]]) -> [f32; 3] {
+    let mut centroid = [0.0; 3];
+    for vertex in vertices {
+        for idx in 0..3 {
+            centroid[idx] += vertex[idx] / vertices.len() as f32;
+        }
+    }
+    centroid
+}
+
 #[inline(always)]
 pub fn float32x3_sub(v1: [f32; 3], v2: [f32; 3]) -> [f32; 3] {
     [v1[0] - v2[0], v1[1] - v2[1], v1[2] - v2[2]]
 }
 
+/// The total area of the triangles of a TriangleList mesh with indices, ignoring the z value.
+pub fn triangle_list_area(mesh: &Mesh) -> f32 {
+    let (Some(vertices), Some(indices)) = (
+        mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+            .and_then(|vertices| vertices.as_float3()),
+        mesh.indices(),
+    ) else {
+        return 0.0;
+    };
+    let indices = indices.iter().collect::<Vec<_>>();
+    indices
+        .chunks_exact(3)
+        .map(|triangle| {
+            let [a, b, c] = [triangle[0], triangle[1], triangle[2]]
+                .map(|idx| Vec3::from(vertices[idx]).truncate());
+            (b - a).perp_dot(c - a).abs() * 0.5
+        })
+        .sum()
+}
+
 #[allow(unused)]
 pub fn debug_meshes(meshes: &[Mesh]) {
     for mesh in meshes {